@@ -0,0 +1,426 @@
+use {
+    crate::{
+        funcs::system_info::humanize,
+        util::{
+            config::expand_home,
+            gauge::Gauge,
+            module::{pick_icon, Module},
+            statics::{BATTERY_EMOJIS, BATTERY_ICONS, CONF, LOCALE},
+            template::fill,
+        },
+    },
+    serde::Serialize,
+    std::{
+        fs,
+        path::{Path, PathBuf},
+    },
+};
+
+/// What the batteries are doing, as the kernel reports it in `status`
+#[derive(Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum State {
+    Charging,
+    Discharging,
+    Full,
+    /// Plugged in but held below full, which some laptops do to spare the battery
+    NotCharging,
+    Unknown,
+}
+
+impl State {
+    fn parse(status: &str) -> State {
+        match status {
+            "Charging" => State::Charging,
+            "Discharging" => State::Discharging,
+            "Full" => State::Full,
+            "Not charging" => State::NotCharging,
+            _ => State::Unknown,
+        }
+    }
+
+    fn key(self) -> &'static str {
+        match self {
+            State::Charging => "battery_charging",
+            State::Discharging => "battery_discharging",
+            State::Full => "battery_full",
+            State::NotCharging => "battery_not_charging",
+            State::Unknown => "battery_unknown",
+        }
+    }
+}
+
+/// The charge of all batteries together
+#[derive(Serialize)]
+pub(crate) struct Battery {
+    pub(crate) percent: f64,
+    pub(crate) state: State,
+    /// Seconds until the batteries are empty, or full while charging
+    pub(crate) remaining: Option<u64>,
+    /// Whether a charger is plugged in, if the machine says
+    pub(crate) ac: Option<bool>,
+}
+
+impl Battery {
+    /// The default format only mentions the time left when the kernel can tell,
+    /// which is the time until full while charging
+    fn format(&self) -> String {
+        match &CONF.battery.format {
+            Some(format) => format.clone(),
+            None if self.remaining.is_some() && self.state == State::Charging => {
+                LOCALE.get("battery_time_charging")
+            }
+            None if self.remaining.is_some() => LOCALE.get("battery_time"),
+            None => LOCALE.get("battery"),
+        }
+    }
+}
+
+impl Module for Battery {
+    fn name(&self) -> &'static str {
+        "battery"
+    }
+
+    fn icon(&self) -> Option<&'static str> {
+        let index = if self.state == State::Charging {
+            5
+        } else {
+            (self.percent.clamp(0.0, 100.0) / 25.0).round() as usize
+        };
+
+        pick_icon(BATTERY_ICONS[index], BATTERY_EMOJIS[index])
+    }
+
+    fn text(&self) -> String {
+        let ac = match self.ac {
+            Some(true) => LOCALE.get("ac_online"),
+            Some(false) => LOCALE.get("ac_offline"),
+            None => String::new(),
+        };
+
+        fill(
+            &self.format(),
+            &[
+                ("percent", format!("{:.0}", self.percent)),
+                ("state", LOCALE.get(self.state.key())),
+                ("time", self.remaining.map(humanize).unwrap_or_default()),
+                ("ac", ac),
+            ],
+        )
+    }
+
+    /// Low charge only matters while nothing charges it
+    fn gauge(&self) -> Option<Gauge> {
+        let conf = &CONF.battery;
+        let discharging = self.state == State::Discharging;
+        Some(Gauge {
+            percent: self.percent,
+            bar: conf.bar,
+            warning: conf.warning.filter(|_| discharging),
+            critical: conf.critical.filter(|_| discharging),
+        })
+    }
+}
+
+/// A file of a power supply, without the trailing newline
+fn read(supply: &Path, file: &str) -> Option<String> {
+    fs::read_to_string(supply.join(file))
+        .ok()
+        .map(|value| value.trim().to_string())
+}
+
+fn read_number(supply: &Path, file: &str) -> Option<f64> {
+    read(supply, file)?.parse().ok()
+}
+
+/// What one battery reports. Batteries give either energy in µWh and power in µW,
+/// or charge in µAh and current in µA.
+struct Reading {
+    state: State,
+    capacity: Option<f64>,
+    now: Option<f64>,
+    full: Option<f64>,
+    rate: Option<f64>,
+}
+
+fn read_battery(supply: &Path) -> Reading {
+    let first = |files: [&str; 2]| files.iter().find_map(|file| read_number(supply, file));
+
+    Reading {
+        state: State::parse(&read(supply, "status").unwrap_or_default()),
+        capacity: read_number(supply, "capacity"),
+        now: first(["energy_now", "charge_now"]),
+        full: first(["energy_full", "charge_full"]),
+        // Some drivers report a negative current while discharging
+        rate: first(["power_now", "current_now"]).map(f64::abs),
+    }
+}
+
+/// The state of several batteries, where one charging or discharging decides it
+fn combined_state(readings: &[Reading]) -> State {
+    let any = |state: State| readings.iter().any(|r| r.state == state);
+
+    if any(State::Charging) {
+        State::Charging
+    } else if any(State::Discharging) {
+        State::Discharging
+    } else if readings.iter().all(|r| r.state == State::Full) {
+        State::Full
+    } else if any(State::NotCharging) {
+        State::NotCharging
+    } else {
+        State::Unknown
+    }
+}
+
+/// The batteries and chargers in `dir`, each a directory named like `BAT0` or `AC`
+fn power_supplies(dir: &Path) -> Vec<PathBuf> {
+    let mut supplies: Vec<PathBuf> = match fs::read_dir(dir) {
+        Ok(entries) => entries.filter_map(|e| e.ok().map(|e| e.path())).collect(),
+        Err(e) => {
+            tracing::debug!("No power supplies in {}: {}", dir.display(), e);
+            Vec::new()
+        }
+    };
+    supplies.sort();
+
+    supplies
+}
+
+/// The batteries and chargers listed in `dir`, a directory like `/sys/class/power_supply`,
+/// or `None` if there is no battery among them
+fn read_power_supplies(dir: &Path) -> Option<Battery> {
+    let supplies = power_supplies(dir);
+    let kind = |supply: &PathBuf| read(supply, "type").unwrap_or_default();
+
+    // Batteries of mice and other devices have a scope of "Device"
+    let readings: Vec<Reading> = supplies
+        .iter()
+        .filter(|supply| kind(supply) == "Battery")
+        .filter(|supply| read(supply, "scope").as_deref() != Some("Device"))
+        .map(|supply| read_battery(supply))
+        .filter(|r| r.capacity.is_some() || (r.now.is_some() && r.full.is_some()))
+        .collect();
+
+    // Desktops have no battery, which is not worth a warning
+    if readings.is_empty() {
+        return None;
+    }
+
+    let chargers: Vec<bool> = supplies
+        .iter()
+        .filter(|supply| matches!(kind(supply).as_str(), "Mains" | "USB"))
+        .filter_map(|supply| read(supply, "online"))
+        .map(|online| online == "1")
+        .collect();
+
+    let sum =
+        |value: fn(&Reading) -> Option<f64>| -> Option<f64> { readings.iter().map(value).sum() };
+    let now = sum(|r| r.now);
+    let full = sum(|r| r.full).filter(|full| *full > 0.0);
+    let rate = sum(|r| r.rate).filter(|rate| *rate > 0.0);
+
+    // The kernel's own `capacity` is preferred over energy or charge, which some drivers
+    // do not scale to what the battery reports. Several batteries are weighted by size.
+    let weighted = sum(|r| Some(r.capacity? * r.full?));
+    let percent = match (sum(|r| r.capacity), weighted, now, full) {
+        (Some(_), Some(weighted), _, Some(full)) => weighted / full,
+        (Some(capacity), _, _, _) => capacity / readings.len() as f64,
+        (None, _, Some(now), Some(full)) => now / full * 100.0,
+        // Some batteries report only `capacity`, the others only energy or charge
+        _ => {
+            let capacities: Vec<f64> = readings.iter().filter_map(|r| r.capacity).collect();
+            capacities.iter().sum::<f64>() / capacities.len().max(1) as f64
+        }
+    };
+
+    let state = combined_state(&readings);
+    let hours = match (state, now, full, rate) {
+        (State::Discharging, Some(now), _, Some(rate)) => Some(now / rate),
+        (State::Charging, Some(now), Some(full), Some(rate)) => Some((full - now).max(0.0) / rate),
+        _ => None,
+    };
+
+    Some(Battery {
+        percent: percent.clamp(0.0, 100.0),
+        state,
+        remaining: hours.map(|hours| (hours * 3600.0).round() as u64),
+        ac: (!chargers.is_empty()).then(|| chargers.contains(&true)),
+    })
+}
+
+#[tracing::instrument]
+pub(crate) fn get_battery() -> Option<Battery> {
+    if !CONF.battery.enabled || !CONF.layout.shows("battery") {
+        return None;
+    }
+
+    read_power_supplies(&expand_home(&CONF.battery.path))
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        std::{env, process},
+    };
+
+    /// A power supply directory under a fresh temporary directory, removed on drop
+    struct Sysfs(PathBuf);
+
+    impl Sysfs {
+        fn new(name: &str) -> Sysfs {
+            let dir = env::temp_dir().join(format!("draconis-{}-{}", name, process::id()));
+            let _ = fs::remove_dir_all(&dir);
+            fs::create_dir_all(&dir).unwrap();
+            Sysfs(dir)
+        }
+
+        fn supply(&self, name: &str, files: &[(&str, &str)]) -> &Sysfs {
+            let supply = self.0.join(name);
+            fs::create_dir_all(&supply).unwrap();
+            for (file, value) in files {
+                fs::write(supply.join(file), format!("{}\n", value)).unwrap();
+            }
+            self
+        }
+
+        fn read(&self) -> Option<Battery> {
+            read_power_supplies(&self.0)
+        }
+    }
+
+    impl Drop for Sysfs {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn discharging_energy_battery() {
+        let sysfs = Sysfs::new("discharging");
+        sysfs.supply(
+            "BAT0",
+            &[
+                ("type", "Battery"),
+                ("status", "Discharging"),
+                ("energy_now", "20000000"),
+                ("energy_full", "50000000"),
+                ("power_now", "8000000"),
+            ],
+        );
+
+        let battery = sysfs.read().unwrap();
+        assert!(battery.state == State::Discharging);
+        assert_eq!(battery.percent, 40.0);
+        // 20 Wh at 8 W last 2.5 hours
+        assert_eq!(battery.remaining, Some(9000));
+        assert_eq!(battery.ac, None);
+    }
+
+    #[test]
+    fn charging_charge_battery() {
+        let sysfs = Sysfs::new("charging");
+        sysfs.supply(
+            "BAT1",
+            &[
+                ("type", "Battery"),
+                ("status", "Charging"),
+                ("charge_now", "3000000"),
+                ("charge_full", "4000000"),
+                ("current_now", "-2000000"),
+            ],
+        );
+
+        let battery = sysfs.read().unwrap();
+        assert!(battery.state == State::Charging);
+        assert_eq!(battery.percent, 75.0);
+        // 1 Ah left to charge at 2 A takes half an hour
+        assert_eq!(battery.remaining, Some(1800));
+    }
+
+    #[test]
+    fn capacity_only() {
+        let sysfs = Sysfs::new("capacity");
+        sysfs.supply(
+            "BAT0",
+            &[("type", "Battery"), ("status", "Full"), ("capacity", "97")],
+        );
+
+        let battery = sysfs.read().unwrap();
+        assert!(battery.state == State::Full);
+        assert_eq!(battery.percent, 97.0);
+        assert_eq!(battery.remaining, None);
+    }
+
+    #[test]
+    fn capacity_before_energy() {
+        let sysfs = Sysfs::new("preferred");
+        sysfs.supply(
+            "BAT0",
+            &[
+                ("type", "Battery"),
+                ("status", "Discharging"),
+                ("capacity", "42"),
+                ("energy_now", "20000000"),
+                ("energy_full", "50000000"),
+            ],
+        );
+
+        assert_eq!(sysfs.read().unwrap().percent, 42.0);
+    }
+
+    #[test]
+    fn device_batteries_are_ignored() {
+        let sysfs = Sysfs::new("device");
+        sysfs.supply(
+            "hidpp_battery_0",
+            &[
+                ("type", "Battery"),
+                ("scope", "Device"),
+                ("status", "Discharging"),
+                ("capacity", "5"),
+            ],
+        );
+        assert!(sysfs.read().is_none());
+
+        sysfs.supply(
+            "BAT0",
+            &[
+                ("type", "Battery"),
+                ("scope", "System"),
+                ("status", "Discharging"),
+                ("capacity", "80"),
+            ],
+        );
+        assert_eq!(sysfs.read().unwrap().percent, 80.0);
+    }
+
+    #[test]
+    fn charger_online() {
+        let sysfs = Sysfs::new("ac");
+        sysfs
+            .supply(
+                "BAT0",
+                &[
+                    ("type", "Battery"),
+                    ("status", "Not charging"),
+                    ("capacity", "60"),
+                ],
+            )
+            .supply("AC", &[("type", "Mains"), ("online", "1")]);
+
+        let battery = sysfs.read().unwrap();
+        assert!(battery.state == State::NotCharging);
+        assert_eq!(battery.ac, Some(true));
+    }
+
+    #[test]
+    fn no_battery() {
+        let sysfs = Sysfs::new("empty");
+        assert!(sysfs.read().is_none());
+
+        sysfs.supply("AC", &[("type", "Mains"), ("online", "1")]);
+        assert!(sysfs.read().is_none());
+    }
+}
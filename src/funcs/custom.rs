@@ -0,0 +1,184 @@
+use {
+    crate::util::{
+        cache::cached,
+        conf_structs::CustomModule,
+        formatting::strip_ansi,
+        module::{Module, MODULE_NAMES},
+        statics::CONF,
+        timeout::{own_group, ProcessGroup},
+    },
+    regex::Regex,
+    serde::Serialize,
+    std::process::{Command, Stdio},
+};
+
+/// The output of a `[[custom]]` command
+#[derive(Serialize)]
+pub(crate) struct Custom {
+    pub(crate) name: &'static str,
+    #[serde(skip)]
+    pub(crate) icon: Option<&'static str>,
+    pub(crate) text: String,
+}
+
+impl Module for Custom {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn icon(&self) -> Option<&'static str> {
+        self.icon.filter(|_| CONF.icons.enabled)
+    }
+
+    fn text(&self) -> String {
+        self.text.clone()
+    }
+}
+
+/// Why the custom module at `index` of `names` is skipped, if it is. The layout and
+/// the cache refer to modules by name, so names have to be unique.
+pub(crate) fn name_conflict(names: &[&str], index: usize) -> Option<&'static str> {
+    let name = names[index];
+    if MODULE_NAMES.contains(&name) {
+        Some("has the name of a built-in one")
+    } else if names[..index].contains(&name) {
+        Some("has the same name as an earlier one")
+    } else {
+        None
+    }
+}
+
+/// The `[[custom]]` modules that are run, without those whose name is taken
+pub(crate) fn custom_modules() -> Vec<&'static CustomModule> {
+    let names: Vec<&str> = CONF.custom.iter().map(|c| c.name.as_str()).collect();
+
+    CONF.custom
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| name_conflict(&names, *i).is_none())
+        .map(|(_, custom)| custom)
+        .collect()
+}
+
+/// Picks the text to show out of a command's output: the first capture group
+/// of `regex` (or the whole match if it has none), or else the first line that
+/// is not empty
+fn extract(custom: &CustomModule, output: &str) -> Option<String> {
+    let output = strip_ansi(output);
+
+    let text = match &custom.regex {
+        Some(pattern) => {
+            let regex = Regex::new(pattern)
+                .map_err(|e| {
+                    tracing::warn!("Invalid regex for custom module {}: {}", custom.name, e)
+                })
+                .ok()?;
+            let captures = regex.captures(&output)?;
+            captures
+                .get(1)
+                .or_else(|| captures.get(0))?
+                .as_str()
+                .trim()
+                .to_string()
+        }
+        None => output
+            .lines()
+            .map(str::trim)
+            .find(|l| !l.is_empty())?
+            .to_string(),
+    };
+
+    Some(text).filter(|t| !t.is_empty())
+}
+
+/// Runs the command of a custom module and extracts its text, without looking at the cache
+pub(crate) async fn run_custom(custom: &CustomModule) -> Option<String> {
+    let mut command = if custom.shell {
+        let mut command = Command::new("sh");
+        // The arguments become $1, $2 and so on in the script
+        command.arg("-c").arg(&custom.command).arg("sh");
+        command
+    } else {
+        Command::new(&custom.command)
+    };
+
+    command.args(&custom.args);
+
+    // Killing only `sh` would leave the commands of a script running after a timeout
+    let child = own_group(command)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| tracing::warn!("Could not run custom module {}: {}", custom.name, e))
+        .ok()?;
+    let group = ProcessGroup::new(&child);
+    let output = child.wait_with_output().await.ok()?;
+    group.release();
+    if !output.status.success() {
+        tracing::info!(
+            "Custom module {} exited with {}",
+            custom.name,
+            output.status
+        );
+    }
+
+    extract(custom, &String::from_utf8_lossy(&output.stdout))
+}
+
+pub(crate) async fn get_custom(custom: &'static CustomModule) -> Option<Custom> {
+    let key = format!("custom-{}", custom.name);
+    let text = cached(&key, custom.cache_ttl, || run_custom(custom)).await?;
+
+    Some(Custom {
+        name: &custom.name,
+        icon: custom.icon.as_deref(),
+        text,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn later_duplicates_and_built_in_names_conflict() {
+        let names = ["vpn", "memory", "git", "vpn", "vpn"];
+        let conflicts: Vec<bool> = (0..names.len())
+            .map(|i| name_conflict(&names, i).is_some())
+            .collect();
+        assert_eq!(conflicts, [false, true, false, true, true]);
+    }
+
+    fn custom(regex: Option<&str>) -> CustomModule {
+        CustomModule {
+            name: "test".into(),
+            regex: regex.map(String::from),
+            ..CustomModule::default()
+        }
+    }
+
+    #[test]
+    fn first_non_empty_line_without_regex() {
+        let output = "\n  \n  wg0 up  \nsecond\n";
+        assert_eq!(extract(&custom(None), output).as_deref(), Some("wg0 up"));
+        assert_eq!(
+            extract(&custom(None), "\x1b[32mgreen\x1b[0m\n").as_deref(),
+            Some("green")
+        );
+        assert_eq!(extract(&custom(None), "\n \n"), None);
+    }
+
+    #[test]
+    fn regex_picks_its_first_group() {
+        let output = "Status: connected\nServer: nl-12\n";
+        let group = custom(Some(r"Server: (\S+)"));
+        assert_eq!(extract(&group, output).as_deref(), Some("nl-12"));
+        // Without a group the whole match is shown
+        let whole = custom(Some(r"nl-\d+"));
+        assert_eq!(extract(&whole, output).as_deref(), Some("nl-12"));
+        assert_eq!(extract(&custom(Some("missing")), output), None);
+        assert_eq!(extract(&custom(Some("(")), output), None);
+    }
+}
@@ -0,0 +1,150 @@
+use {
+    crate::util::{
+        conf_structs::{Overflow, Pick},
+        config::expand_home,
+        formatting::display_width,
+        module::{pick_icon, Module},
+        statics::{CONF, MISC_EMOJIS, MISC_ICONS},
+    },
+    chrono::{Datelike, Local},
+    rand::{seq::SliceRandom, thread_rng},
+    serde::Serialize,
+    std::{
+        fs,
+        path::{Path, PathBuf},
+    },
+};
+
+/// Where fortune files are installed, used when `[fortune] path` is not set
+static FORTUNE_DIRS: [&str; 3] = [
+    "/usr/share/games/fortunes",
+    "/usr/share/fortune",
+    "/usr/share/fortunes",
+];
+
+#[derive(Serialize)]
+pub(crate) struct Fortune(pub(crate) String);
+
+impl Module for Fortune {
+    fn name(&self) -> &'static str {
+        "fortune"
+    }
+
+    fn icon(&self) -> Option<&'static str> {
+        pick_icon(MISC_ICONS[6], MISC_EMOJIS[6])
+    }
+
+    fn text(&self) -> String {
+        self.0.clone()
+    }
+
+    /// Quotes are rarely short enough for one row, so they are wrapped unless
+    /// `[layout.module_overflow]` says otherwise
+    fn overflow(&self) -> Overflow {
+        CONF.layout
+            .module_overflow
+            .get("fortune")
+            .copied()
+            .unwrap_or(Overflow::Wrap)
+    }
+}
+
+/// The fortune files in `path`, which is either one of them or a directory.
+/// The `.dat` and `.u8` files `strfile` puts next to them are skipped.
+fn fortune_files(path: &Path) -> Vec<PathBuf> {
+    if !path.is_dir() {
+        return vec![path.to_path_buf()];
+    }
+
+    let mut files: Vec<PathBuf> = match fs::read_dir(path) {
+        Ok(entries) => entries
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .filter(|p| p.is_file())
+            .filter(|p| !matches!(p.extension().and_then(|e| e.to_str()), Some("dat" | "u8")))
+            .collect(),
+        Err(e) => {
+            tracing::warn!("Could not read fortunes in {}: {}", path.display(), e);
+            Vec::new()
+        }
+    };
+    // Keeps the daily pick the same no matter the order the directory is listed in
+    files.sort();
+
+    files
+}
+
+/// The quotes in a fortune file, which are separated by lines holding only `%`.
+/// Line breaks and indentation are collapsed into single spaces.
+fn read_quotes(path: &Path) -> Vec<String> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) => {
+            tracing::info!("Skipping fortune file {}: {}", path.display(), e);
+            return Vec::new();
+        }
+    };
+
+    let mut quotes = vec![String::new()];
+    for line in content.lines() {
+        if line.trim_end() == "%" {
+            quotes.push(String::new());
+            continue;
+        }
+        for word in line.split_whitespace() {
+            let quote = quotes.last_mut().unwrap();
+            if !quote.is_empty() {
+                quote.push(' ');
+            }
+            quote.push_str(word);
+        }
+    }
+    quotes.retain(|quote| !quote.is_empty());
+
+    quotes
+}
+
+#[tracing::instrument]
+pub(crate) fn get_fortune_blocking() -> Option<Fortune> {
+    if !CONF.fortune.enabled || !CONF.layout.shows("fortune") {
+        return None;
+    }
+
+    let path = match &CONF.fortune.path {
+        Some(path) => expand_home(path),
+        None => PathBuf::from(FORTUNE_DIRS.iter().find(|dir| Path::new(dir).is_dir())?),
+    };
+
+    // Only one file is read, picked the same way as the quote in it
+    let mut files = fortune_files(&path);
+    let day = Local::now().num_days_from_ce().unsigned_abs() as usize;
+    let today = day % files.len().max(1);
+    match CONF.fortune.pick {
+        Pick::Random => files.shuffle(&mut thread_rng()),
+        Pick::Daily => files.rotate_left(today),
+    }
+    // Files that can not be read or hold no quotes are passed over
+    let quotes = files
+        .iter()
+        .map(|file| read_quotes(file))
+        .find(|quotes| !quotes.is_empty())?;
+
+    // Fall back to long quotes rather than showing none
+    let max_length = CONF.fortune.max_length;
+    let short: Vec<&String> = quotes
+        .iter()
+        .filter(|quote| max_length == 0 || display_width(quote) <= max_length)
+        .collect();
+    let quotes: Vec<&String> = if short.is_empty() {
+        quotes.iter().collect()
+    } else {
+        short
+    };
+
+    let quote = match CONF.fortune.pick {
+        Pick::Random => quotes.choose(&mut thread_rng())?,
+        // Each file comes up every `files.len()` days, with its next quote
+        Pick::Daily => quotes.get(day / files.len() % quotes.len())?,
+    };
+
+    Some(Fortune(quote.to_string()))
+}
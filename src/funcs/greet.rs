@@ -1,21 +1,136 @@
 use {
-    crate::util::statics::{CONF, GREET_EMOJIS, GREET_ICONS},
-    chrono::{Local, Timelike},
+    crate::util::{
+        conf_structs::GreetingWindows,
+        formatting::calc_top,
+        module::{pick_icon, Module},
+        statics::{CONF, GREET_EMOJIS, GREET_ICONS, LOCALE},
+        template::fill,
+    },
+    chrono::{Datelike, Local, NaiveDate, Timelike},
+    rand::{seq::SliceRandom, thread_rng},
+    serde::Serialize,
     sys_info::hostname,
     whoami::{realname, username},
 };
 
+#[derive(Serialize)]
+pub(crate) struct Hostname(pub(crate) String);
+
+impl Module for Hostname {
+    fn name(&self) -> &'static str {
+        "hostname"
+    }
+
+    fn text(&self) -> String {
+        self.0.clone()
+    }
+
+    fn render(&self, _label_width: usize) -> Vec<String> {
+        vec![calc_top(Some(&self.0))]
+    }
+}
+
+#[derive(Serialize)]
+pub(crate) struct Greeting {
+    pub(crate) hour: u32,
+    pub(crate) name: String,
+    pub(crate) greeting: String,
+}
+
+/// The part of the day `hour` is in: morning, afternoon, evening or night
+fn period(hour: u32, windows: &GreetingWindows) -> usize {
+    let starts = [
+        windows.morning,
+        windows.afternoon,
+        windows.evening,
+        windows.night,
+    ];
+
+    // The part that started last, or the one that lasts past midnight
+    (0..4)
+        .filter(|&i| starts[i] <= hour)
+        .max_by_key(|&i| starts[i])
+        .or_else(|| (0..4).max_by_key(|&i| starts[i]))
+        .unwrap_or(3)
+}
+
+/// Whether `date`, given as `MM-DD` or `YYYY-MM-DD`, is today
+fn is_today(date: &str, today: NaiveDate) -> bool {
+    let parsed = match date.len() {
+        5 => NaiveDate::parse_from_str(&format!("{}-{}", today.year(), date), "%Y-%m-%d"),
+        _ => NaiveDate::parse_from_str(date, "%Y-%m-%d"),
+    };
+
+    match parsed {
+        Ok(parsed) => parsed == today,
+        // There is no 02-29 in most years, which is fine
+        Err(_) if date == "02-29" => false,
+        Err(e) => {
+            tracing::warn!("Ignoring greeting date {}: {}", date, e);
+            false
+        }
+    }
+}
+
+/// The messages of `[[greeting.events]]` and the birthday that fall on today
+fn events(today: NaiveDate) -> Vec<String> {
+    let greeting = &CONF.greeting;
+    let birthday = greeting
+        .birthday
+        .as_deref()
+        .filter(|date| is_today(date, today))
+        .map(|_| LOCALE.get("greeting_birthday"));
+
+    greeting
+        .events
+        .iter()
+        .filter(|event| is_today(&event.date, today))
+        .map(|event| event.message.clone())
+        .chain(birthday)
+        .collect()
+}
+
+impl Greeting {
+    fn period(&self) -> usize {
+        period(self.hour, &CONF.greeting.windows)
+    }
+}
+
+impl Module for Greeting {
+    fn name(&self) -> &'static str {
+        "greeting"
+    }
+
+    fn icon(&self) -> Option<&'static str> {
+        pick_icon(GREET_ICONS[self.period()], GREET_EMOJIS[self.period()])
+    }
+
+    fn text(&self) -> String {
+        fill(
+            &CONF.greeting.format,
+            &[
+                ("greeting", self.greeting.clone()),
+                ("name", self.name.clone()),
+            ],
+        )
+    }
+}
+
 #[tracing::instrument]
-pub(crate) fn get_hostname() -> Option<String> {
+pub(crate) fn get_hostname() -> Option<Hostname> {
+    if !CONF.layout.shows("hostname") {
+        return None;
+    }
+
     match &CONF.main.hostname {
-        Some(hostname) => Some(hostname.to_string()),
-        None => Some(format!("{}@{}", username(), hostname().ok()?)),
+        Some(hostname) => Some(Hostname(hostname.to_string())),
+        None => Some(Hostname(format!("{}@{}", username(), hostname().ok()?))),
     }
 }
 
 #[tracing::instrument]
-pub(crate) fn greeting() -> Option<String> {
-    if !CONF.greeting.enabled {
+pub(crate) fn greeting() -> Option<Greeting> {
+    if !CONF.greeting.enabled || !CONF.layout.shows("greeting") {
         return None;
     }
 
@@ -25,35 +140,82 @@ pub(crate) fn greeting() -> Option<String> {
         CONF.main.name.as_ref()?.to_string()
     };
 
-    let phrase = if CONF.icons.enabled {
-        match CONF.icons.kind.as_deref() {
-            Some("emoji") => match Local::now().hour() {
-                6..=11 => format!("{} Good morning", GREET_EMOJIS[0]),
-                12..=17 => format!("{} Good afternoon", GREET_EMOJIS[1]),
-                18..=22 => format!("{} Good evening", GREET_EMOJIS[2]),
-                _ => format!("{} Good night", GREET_EMOJIS[3]),
-            },
-            Some("normal") => match Local::now().hour() {
-                6..=11 => format!("{} Good morning", GREET_ICONS[0]),
-                12..=17 => format!("{} Good afternoon", GREET_ICONS[1]),
-                18..=22 => format!("{} Good evening", GREET_ICONS[2]),
-                _ => format!("{} Good night", GREET_ICONS[3]),
-            },
-            Some(&_) | None => match Local::now().hour() {
-                6..=11 => "Good morning".to_string(),
-                12..=17 => "Good afternoon".to_string(),
-                18..=22 => "Good evening".to_string(),
-                _ => "Good night".to_string(),
-            },
-        }
-    } else {
-        match Local::now().hour() {
-            6..=11 => "Good morning".to_string(),
-            12..=17 => "Good afternoon".to_string(),
-            18..=22 => "Good evening".to_string(),
-            _ => "Good night".to_string(),
-        }
+    let now = Local::now();
+    let phrases = &CONF.greeting.phrases;
+    let (phrases, key) = match period(now.hour(), &CONF.greeting.windows) {
+        0 => (&phrases.morning, "greeting_morning"),
+        1 => (&phrases.afternoon, "greeting_afternoon"),
+        2 => (&phrases.evening, "greeting_evening"),
+        _ => (&phrases.night, "greeting_night"),
     };
 
-    Some(format!("{}, {}", phrase, name))
+    // Special days win over the phrases of the day
+    let mut rng = thread_rng();
+    let greeting = events(now.naive_local().date())
+        .choose(&mut rng)
+        .or_else(|| phrases.choose(&mut rng))
+        .cloned()
+        .unwrap_or_else(|| LOCALE.get(key));
+
+    Some(Greeting {
+        hour: now.hour(),
+        name,
+        greeting,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn windows(morning: u32, afternoon: u32, evening: u32, night: u32) -> GreetingWindows {
+        GreetingWindows {
+            morning,
+            afternoon,
+            evening,
+            night,
+        }
+    }
+
+    #[test]
+    fn night_spans_midnight() {
+        let windows = windows(6, 12, 18, 23);
+        let periods: Vec<usize> = [23, 0, 3, 5, 6, 11, 12, 18, 22]
+            .iter()
+            .map(|hour| period(*hour, &windows))
+            .collect();
+        assert_eq!(periods, [3, 3, 3, 3, 0, 0, 1, 2, 2]);
+    }
+
+    #[test]
+    fn night_after_midnight() {
+        // The night starts at 1, so the evening lasts past midnight
+        let windows = windows(7, 12, 19, 1);
+        assert_eq!(period(0, &windows), 2);
+        assert_eq!(period(1, &windows), 3);
+        assert_eq!(period(6, &windows), 3);
+        assert_eq!(period(7, &windows), 0);
+    }
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn dates_every_year_or_once() {
+        assert!(is_today("10-18", date(2026, 10, 18)));
+        assert!(is_today("10-18", date(2030, 10, 18)));
+        assert!(!is_today("10-19", date(2026, 10, 18)));
+        assert!(is_today("2026-10-18", date(2026, 10, 18)));
+        assert!(!is_today("2025-10-18", date(2026, 10, 18)));
+    }
+
+    #[test]
+    fn leap_day() {
+        assert!(is_today("02-29", date(2028, 2, 29)));
+        // Not a leap year, so there is no such day rather than an error
+        assert!(!is_today("02-29", date(2026, 2, 28)));
+        assert!(!is_today("02-29", date(2026, 3, 1)));
+        assert!(!is_today("13-01", date(2026, 1, 13)));
+    }
 }
@@ -1,179 +1,199 @@
 use std::process::Command;
 
 use {
-    crate::util::statics::{CONF, TIME_EMOJIS, TIME_ICONS, WEATHER_EMOJIS, WEATHER_ICONS},
-    chrono::{Local, Timelike},
+    crate::util::{
+        cache::cached,
+        module::{pick_icon, Module},
+        statics::{
+            CONF, LOCALE, MISC_EMOJIS, MISC_ICONS, TIME_EMOJIS, TIME_ICONS, WEATHER_EMOJIS,
+            WEATHER_ICONS,
+        },
+        template::fill,
+        timeout::detached,
+    },
+    chrono::{DateTime as ChronoDateTime, Datelike, Local, Timelike},
     mpris::PlayerFinder,
-    openweathermap::weather,
-    substring::Substring,
+    openweathermap::blocking::weather,
+    serde::{Deserialize, Serialize},
 };
 
+#[derive(Serialize)]
+pub(crate) struct Song {
+    pub(crate) artist: String,
+    pub(crate) title: String,
+}
+
+impl Module for Song {
+    fn name(&self) -> &'static str {
+        "song"
+    }
+
+    fn icon(&self) -> Option<&'static str> {
+        pick_icon(MISC_ICONS[5], MISC_EMOJIS[5])
+    }
+
+    fn text(&self) -> String {
+        fill(
+            &CONF.song.format,
+            &[
+                ("artist", self.artist.clone()),
+                ("title", self.title.clone()),
+            ],
+        )
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub(crate) struct Weather {
+    pub(crate) main: String,
+    pub(crate) temp: f64,
+    pub(crate) units: String,
+    pub(crate) icon_code: String,
+}
+
+impl Module for Weather {
+    fn name(&self) -> &'static str {
+        "weather"
+    }
+
+    fn icon(&self) -> Option<&'static str> {
+        let index = match self.icon_code.as_ref() {
+            "01d" => 0, // Clear sky
+            "01n" => 1,
+            "02d" => 2, // Few clouds
+            "02n" => 3,
+            "03d" => 4, // Scattered clouds
+            "03n" => 5,
+            "04d" => 6, // Broken clouds
+            "04n" => 7,
+            "09d" => 8, // Shower rain
+            "09n" => 9,
+            "10d" => 10, // Rain
+            "10n" => 11,
+            "11d" => 12, // Thunderstorm
+            "11n" => 13,
+            "13d" => 14, // Snow
+            "13n" => 15,
+            "40d" => 16, // Mist
+            "40n" => 17,
+            "50d" => 18, // Fog
+            "50n" => 19,
+            _ => 20, // Unknown
+        };
+
+        pick_icon(WEATHER_ICONS[index], WEATHER_EMOJIS[index])
+    }
+
+    fn text(&self) -> String {
+        let deg = if self.units.trim_matches('\"') == "imperial" {
+            "F"
+        } else {
+            "C"
+        };
+
+        fill(
+            &CONF.weather.format,
+            &[
+                ("main", self.main.clone()),
+                ("temp", format!("{:.0}", self.temp)),
+                ("unit", deg.to_string()),
+            ],
+        )
+    }
+}
+
+#[derive(Serialize)]
+pub(crate) struct DateTime {
+    pub(crate) time: ChronoDateTime<Local>,
+    pub(crate) kind: String,
+}
+
+impl Module for DateTime {
+    fn name(&self) -> &'static str {
+        "datetime"
+    }
+
+    fn icon(&self) -> Option<&'static str> {
+        let index = (self.time.hour() % 12) as usize;
+        pick_icon(TIME_ICONS[index], TIME_EMOJIS[index])
+    }
+
+    fn text(&self) -> String {
+        let dt = self.time;
+        let time = match self.kind.as_str() {
+            "12h" => dt.format("%l:%M %p").to_string(),
+            "24h" => dt.format("%H:%M").to_string(),
+            _ => "off".to_string(),
+        };
+        let mut values = vec![
+            ("time", time.trim_start_matches(' ').to_string()),
+            ("weekday", LOCALE.weekday(dt.weekday().number_from_monday())),
+            ("month", LOCALE.month(dt.month())),
+            ("day", LOCALE.ordinal(dt.day())),
+            ("year", dt.format("%Y").to_string()),
+        ];
+        // The locale's date format can use the same placeholders, except `{date}` itself
+        let date = fill(&LOCALE.get("date"), &values);
+        values.push(("date", date));
+
+        fill(&CONF.time.format, &values)
+    }
+}
+
 #[tracing::instrument]
-pub(crate) fn get_song() -> Option<String> {
-    if !CONF.song.enabled {
+pub(crate) fn get_song() -> Option<Song> {
+    if !CONF.song.enabled || !CONF.layout.shows("song") {
         return None;
     }
 
-    let songname;
     if CONF.song.mode == Some("playerctl".into()) {
         let song = Command::new("playerctl")
-            .args(&["metadata", "-f", "{{ artist }} - {{ title }}"])
+            .args(["metadata", "-f", "{{ artist }}\t{{ title }}"])
             .output()
-            .unwrap();
-        songname = String::from_utf8_lossy(&song.stdout).to_string();
-        if songname.is_empty() {
-            return None;
-        }
+            .ok()?;
+        let song = String::from_utf8_lossy(&song.stdout);
+        let (artist, title) = song.trim_end_matches('\n').split_once('\t')?;
+
+        Some(Song {
+            artist: artist.to_string(),
+            title: title.to_string(),
+        })
     } else {
         let player = PlayerFinder::new().ok()?.find_all().ok()?;
         let song = player.first()?.get_metadata().ok()?; // this is blocking
-        let artists = song.artists()?.join(", ");
-        songname = format!("{} - {}", artists, song.title()?);
-    }
 
-    if CONF.icons.enabled {
-        match CONF.icons.kind.as_deref() {
-            Some("emoji") => {
-                if songname.len() > CONF.util.width as usize {
-                    Some(format!(
-                        "{}...",
-                        songname.substring(0, (CONF.util.width - 14) as usize)
-                    ))
-                } else {
-                    Some(songname.trim_end_matches('\n').to_string())
-                }
-            }
-            Some(&_) | None => {
-                if songname.len() > CONF.util.width as usize {
-                    Some(format!(
-                        "{}...",
-                        songname.substring(0, (CONF.util.width - 14) as usize)
-                    ))
-                } else {
-                    Some(songname.trim_end_matches('\n').to_string())
-                }
-            }
-        }
-    } else if songname.len() > CONF.util.width as usize {
-        Some(format!(
-            "{}...",
-            songname.substring(0, (CONF.util.width - 14) as usize)
-        ))
-    } else {
-        Some(songname.trim_end_matches('\n').to_string())
+        Some(Song {
+            artist: song.artists()?.join(", "),
+            title: song.title()?.to_string(),
+        })
     }
 }
 
 #[tracing::instrument]
-pub(crate) async fn get_weather() -> Option<String> {
-    if CONF.weather.values.api_key.is_none()
-        || CONF.weather.values.lang.is_none()
-        || CONF.weather.values.location.is_none()
-        || CONF.weather.values.units.is_none()
-        || !CONF.weather.enabled
-    {
+pub(crate) async fn get_weather() -> Option<Weather> {
+    if !CONF.weather.enabled || !CONF.layout.shows("weather") {
         return None;
     }
 
-    let api_key = CONF.weather.values.api_key.as_ref().unwrap().as_str();
-    let lang = CONF.weather.values.lang.as_ref().unwrap().as_str();
-    let location = CONF.weather.values.location.as_ref().unwrap().as_str();
-    let units = CONF.weather.values.units.as_ref().unwrap().as_str();
-
-    match &weather(location, units, lang, api_key).await {
-        Ok(current) => {
-            let deg = if units.trim_matches('\"') == "imperial" {
-                "F"
-            } else {
-                "C"
-            };
-            let icon_code = &current.weather[0].icon;
-            let icon = if CONF.icons.enabled {
-                match CONF.icons.kind.as_deref() {
-                    Some("emoji") => {
-                        match icon_code.as_ref() {
-                            "01d" => WEATHER_EMOJIS[0], // Clear sky
-                            "01n" => WEATHER_EMOJIS[1],
-                            "02d" => WEATHER_EMOJIS[2], // Few clouds
-                            "02n" => WEATHER_EMOJIS[3],
-                            "03d" => WEATHER_EMOJIS[4], // Scattered clouds
-                            "03n" => WEATHER_EMOJIS[5],
-                            "04d" => WEATHER_EMOJIS[6], // Broken clouds
-                            "04n" => WEATHER_EMOJIS[7],
-                            "09d" => WEATHER_EMOJIS[8], // Shower rain
-                            "09n" => WEATHER_EMOJIS[9],
-                            "10d" => WEATHER_EMOJIS[10], // Rain
-                            "10n" => WEATHER_EMOJIS[11],
-                            "11d" => WEATHER_EMOJIS[12], // Thunderstorm
-                            "11n" => WEATHER_EMOJIS[13],
-                            "13d" => WEATHER_EMOJIS[14], // Snow
-                            "13n" => WEATHER_EMOJIS[15],
-                            "40d" => WEATHER_EMOJIS[16], // Mist
-                            "40n" => WEATHER_EMOJIS[17],
-                            "50d" => WEATHER_EMOJIS[18], // Fog
-                            "50n" => WEATHER_EMOJIS[19],
-                            _ => WEATHER_EMOJIS[20], // Unknown
-                        }
-                    }
-                    Some("normal") => match icon_code.as_ref() {
-                        "01d" => WEATHER_ICONS[0],
-                        "01n" => WEATHER_ICONS[1],
-                        "02d" => WEATHER_ICONS[2],
-                        "02n" => WEATHER_ICONS[3],
-                        "03d" => WEATHER_ICONS[4],
-                        "03n" => WEATHER_ICONS[5],
-                        "04d" => WEATHER_ICONS[6],
-                        "04n" => WEATHER_ICONS[7],
-                        "09d" => WEATHER_ICONS[8],
-                        "09n" => WEATHER_ICONS[9],
-                        "10d" => WEATHER_ICONS[10],
-                        "10n" => WEATHER_ICONS[11],
-                        "11d" => WEATHER_ICONS[12],
-                        "11n" => WEATHER_ICONS[13],
-                        "13d" => WEATHER_ICONS[14],
-                        "13n" => WEATHER_ICONS[15],
-                        "40d" => WEATHER_ICONS[16],
-                        "40n" => WEATHER_ICONS[17],
-                        "50d" => WEATHER_ICONS[18],
-                        "50n" => WEATHER_ICONS[19],
-                        _ => WEATHER_ICONS[20],
-                    },
-                    Some(&_) | None => "",
-                }
-            } else {
-                ""
-            };
-
-            let main = current.weather[0].main.to_string();
-            let temp = current.main.temp.to_string();
-
-            if CONF.icons.enabled {
-                match CONF.icons.kind.as_deref() {
-                    Some("emoji") | Some("normal") => Some(format!(
-                        "│ {} {} {}°{}",
-                        icon,
-                        main,
-                        temp.substring(0, 2),
-                        deg
-                    )),
-                    Some(&_) | None => Some(format!(
-                        "│{} {} {}°{}",
-                        icon,
-                        main,
-                        temp.substring(0, 2),
-                        deg
-                    )),
-                }
-            } else {
-                Some(format!(
-                    "│{} {} {}°{}",
-                    icon,
-                    main,
-                    temp.substring(0, 2),
-                    deg
-                ))
-            }
-        }
+    cached("weather", CONF.weather.cache_ttl, fetch_weather).await
+}
+
+/// `openweathermap` spins on its request without ever yielding and retries failed
+/// ones forever, so it runs on a thread of its own that `limit` can give up on
+#[tracing::instrument]
+pub(crate) async fn fetch_weather() -> Option<Weather> {
+    let api_key = CONF.weather.values.api_key.as_deref()?;
+    let lang = CONF.weather.values.lang.as_str();
+    let location = CONF.weather.values.location.as_deref()?;
+    let units = CONF.weather.values.units.as_str();
+
+    detached(move || match weather(location, units, lang, api_key) {
+        Ok(current) => Some(Weather {
+            main: current.weather[0].main.to_string(),
+            temp: current.main.temp,
+            units: units.to_string(),
+            icon_code: current.weather[0].icon.to_string(),
+        }),
         Err(e) => {
             tracing::warn!(
                 "Could not fetch weather because: {} - maybe you forgot an API key?",
@@ -181,73 +201,18 @@ pub(crate) async fn get_weather() -> Option<String> {
             );
             None
         }
-    }
+    })
+    .await
 }
 
 #[tracing::instrument]
-pub(crate) fn get_datetime() -> Option<String> {
-    if !CONF.time.enabled {
+pub(crate) fn get_datetime() -> Option<DateTime> {
+    if !CONF.time.enabled || !CONF.layout.shows("datetime") {
         return None;
     }
 
-    let dt = Local::now();
-    let time = match CONF.time.kind.as_deref()? {
-        "12h" => dt.format("%l:%M %p").to_string(),
-        "24h" => dt.format("%H:%M").to_string(),
-        _ => "off".to_string(),
-    };
-    let day = dt.format("%e").to_string();
-    let date = match day.trim_start_matches(' ') {
-        "1" | "21" | "31 " => format!("{} {}st", dt.format("%B"), day.trim_start_matches(' ')),
-        "2" | "22" => format!("{} {}nd", dt.format("%B"), day.trim_start_matches(' ')),
-        "3" | "23" => format!("{} {}rd", dt.format("%B"), day.trim_start_matches(' ')),
-        _ => format!("{} {}th", dt.format("%B"), day.trim_start_matches(' ')),
-    };
-    let time_icon = if CONF.icons.enabled {
-        match CONF.icons.kind.as_deref() {
-            Some("emoji") => {
-                let index: usize = if dt.hour() >= 12 {
-                    (dt.hour() - 12).try_into().unwrap()
-                } else {
-                    dt.hour().try_into().unwrap()
-                };
-                TIME_EMOJIS[index.min(11)]
-            }
-            Some("normal") => {
-                let index: usize = if dt.hour() >= 12 {
-                    (dt.hour() - 12).try_into().unwrap()
-                } else {
-                    dt.hour().try_into().unwrap()
-                };
-                TIME_ICONS[index.min(11)]
-            }
-            Some(&_) | None => "",
-        }
-    } else {
-        ""
-    };
-
-    if CONF.icons.enabled {
-        match CONF.icons.kind.as_deref() {
-            Some("emoji") | Some("normal") => Some(format!(
-                "│ {} {}, {}",
-                time_icon,
-                date,
-                time.trim_start_matches(' ')
-            )),
-            Some(&_) | None => Some(format!(
-                "│{} {}, {}",
-                time_icon,
-                date,
-                time.trim_start_matches(' ')
-            )),
-        }
-    } else {
-        Some(format!(
-            "│{} {}, {}",
-            time_icon,
-            date,
-            time.trim_start_matches(' ')
-        ))
-    }
+    Some(DateTime {
+        time: Local::now(),
+        kind: CONF.time.kind.clone(),
+    })
 }
@@ -1,3 +1,6 @@
+pub mod battery;
+pub mod custom;
+pub mod fortune;
 pub mod greet;
 pub mod misc;
 pub mod pkgs;
@@ -1,14 +1,67 @@
 use std::env;
 
 use {
-    crate::util::statics::{CONF, PACKAGE_EMOJIS, PACKAGE_ICONS},
-    std::process::Stdio,
+    crate::util::{
+        cache::cached,
+        module::{pick_icon, Module},
+        statics::{CONF, LOCALE, PACKAGE_EMOJIS, PACKAGE_ICONS},
+        template::fill,
+        timeout::{own_group, ProcessGroup},
+    },
+    futures::future::join_all,
+    serde::{Deserialize, Serialize},
+    std::process::{Command, Stdio},
     tokio::{
         io::{AsyncBufReadExt, BufReader},
-        process::{ChildStdout, Command},
+        process::ChildStdout,
     },
 };
 
+#[derive(Serialize, Deserialize)]
+pub(crate) struct Updates(pub(crate) i32);
+
+impl Module for Updates {
+    fn name(&self) -> &'static str {
+        "updates"
+    }
+
+    fn icon(&self) -> Option<&'static str> {
+        pick_icon(
+            PACKAGE_ICONS[self.0.clamp(0, 10) as usize],
+            PACKAGE_EMOJIS[self.0.clamp(0, 11) as usize],
+        )
+    }
+
+    fn text(&self) -> String {
+        if let Some(format) = &CONF.packages.update_count.format {
+            return fill(format, &[("count", self.0.to_string())]);
+        }
+
+        LOCALE.count("updates", self.0.max(0) as u64)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub(crate) struct Packages(pub(crate) i32);
+
+impl Module for Packages {
+    fn name(&self) -> &'static str {
+        "packages"
+    }
+
+    fn icon(&self) -> Option<&'static str> {
+        pick_icon(PACKAGE_ICONS[11], PACKAGE_EMOJIS[12])
+    }
+
+    fn text(&self) -> String {
+        if let Some(format) = &CONF.packages.package_count.format {
+            return fill(format, &[("count", self.0.to_string())]);
+        }
+
+        LOCALE.count("packages", self.0.max(0) as u64)
+    }
+}
+
 #[derive(Debug)]
 enum CommandKind {
     Pacman,
@@ -55,7 +108,7 @@ fn check_update_commmand(command: String) -> Option<(CommandKind, Command)> {
         "pacman" => (CommandKind::Pacman, Command::new("checkupdates")),
         "apt" => (CommandKind::Apt, {
             let mut command = Command::new("apt");
-            command.args(&["list", "-u"]);
+            command.args(["list", "-u"]);
 
             command
         }),
@@ -66,12 +119,12 @@ fn check_update_commmand(command: String) -> Option<(CommandKind, Command)> {
         }),
         "portage" => (CommandKind::Portage, {
             let mut command = Command::new("eix");
-            command.args(&["-u", "--format", "'<installedversions:nameversion>'"]);
+            command.args(["-u", "--format", "'<installedversions:nameversion>'"]);
             command
         }),
         "apk" => (CommandKind::Apk, {
             let mut command = Command::new("apk");
-            command.args(&["-u", "list"]);
+            command.args(["-u", "list"]);
             command
         }),
         "dnf" => (CommandKind::Dnf, {
@@ -90,40 +143,46 @@ fn check_update_commmand(command: String) -> Option<(CommandKind, Command)> {
 
 #[tracing::instrument]
 async fn do_update_counting(arg: String) -> Option<i32> {
-    let (kind, mut command) = check_update_commmand(arg)?;
-    let reader = command
+    let (kind, command) = check_update_commmand(arg)?;
+    // The child and what it started are killed if this future is dropped because it took too long
+    let mut child = own_group(command)
         .stderr(Stdio::null())
         .stdout(Stdio::piped())
+        .kill_on_drop(true)
         .spawn()
-        .ok()?
-        .stdout
-        .take()?;
+        .ok()?;
+    let group = ProcessGroup::new(&child);
+    let reader = child.stdout.take()?;
 
     let fs = BufReader::new(reader);
-    match kind {
+    let count = match kind {
         CommandKind::Apt => count_lines(2, fs).await,
         CommandKind::Portage => Some(0), // FIXME: Portage needs a proper update count command
         CommandKind::Dnf => count_lines(3, fs).await,
         _ => count_lines(0, fs).await,
+    };
+    if child.wait().await.is_ok() {
+        group.release();
     }
+
+    count
 }
 
 async fn check_updates() -> Option<i32> {
     match &CONF.packages.package_managers {
         Some(toml::Value::Array(pm)) => {
-            let mut handles = Vec::new();
-
-            for arg in pm {
-                if let toml::Value::String(string) = arg {
-                    let handle = tokio::spawn(do_update_counting(string.clone()));
-                    handles.push(handle);
-                }
-            }
+            // Not spawned, so that all commands are killed together if this takes too long
+            let counts = join_all(
+                pm.iter()
+                    .filter_map(toml::Value::as_str)
+                    .map(|string| do_update_counting(string.to_string())),
+            )
+            .await;
 
             let mut total_updates = 0;
 
-            for handle in handles {
-                total_updates += handle.await.ok()??;
+            for count in counts {
+                total_updates += count?;
             }
 
             Some(total_updates)
@@ -134,43 +193,21 @@ async fn check_updates() -> Option<i32> {
 }
 
 #[tracing::instrument]
-pub(crate) async fn count_updates() -> Option<String> {
-    let count = check_updates().await?;
-    let updates = if CONF.icons.enabled {
-        match CONF.icons.kind.as_deref() {
-            Some("emoji") => match count {
-                0 => format!("{} Up to date", PACKAGE_EMOJIS[0]),
-                1 => format!("{} 1 update", PACKAGE_EMOJIS[1]),
-                2 => format!("{} 2 updates", PACKAGE_EMOJIS[2]),
-                3 => format!("{} 3 updates", PACKAGE_EMOJIS[3]),
-                4 => format!("{} 4 updates", PACKAGE_EMOJIS[4]),
-                5 => format!("{} 5 updates", PACKAGE_EMOJIS[5]),
-                6 => format!("{} 6 updates", PACKAGE_EMOJIS[6]),
-                7 => format!("{} 7 updates", PACKAGE_EMOJIS[7]),
-                8 => format!("{} 8 updates", PACKAGE_EMOJIS[8]),
-                9 => format!("{} 9 updates", PACKAGE_EMOJIS[9]),
-                10 => format!("{} 10 updates", PACKAGE_EMOJIS[10]),
-                _ => format!("{} {} updates", PACKAGE_EMOJIS[11], count),
-            },
-            Some("normal") => match count {
-                0 => format!("{} Up to date", PACKAGE_ICONS[0]),
-                1 => format!("{} 1 update", PACKAGE_ICONS[1]),
-                2 => format!("{} 2 updates", PACKAGE_ICONS[2]),
-                3 => format!("{} 3 updates", PACKAGE_ICONS[3]),
-                4 => format!("{} 4 updates", PACKAGE_ICONS[4]),
-                5 => format!("{} 5 updates", PACKAGE_ICONS[5]),
-                6 => format!("{} 6 updates", PACKAGE_ICONS[6]),
-                7 => format!("{} 7 updates", PACKAGE_ICONS[7]),
-                8 => format!("{} 8 updates", PACKAGE_ICONS[8]),
-                9 => format!("{} 9 updates", PACKAGE_ICONS[9]),
-                _ => format!("{} {} updates", PACKAGE_ICONS[10], count),
-            },
-            Some(&_) | None => format!("{} updates", count),
-        }
-    } else {
-        format!("{} updates", count)
-    };
-    Some(format!("│ {}", updates))
+pub(crate) async fn count_updates() -> Option<Updates> {
+    if !CONF.packages.update_count.enabled || !CONF.layout.shows("updates") {
+        return None;
+    }
+
+    cached(
+        "updates",
+        CONF.packages.update_count.cache_ttl,
+        collect_updates,
+    )
+    .await
+}
+
+pub(crate) async fn collect_updates() -> Option<Updates> {
+    Some(Updates(check_updates().await?))
 }
 
 fn check_installed_command(command: String) -> Option<(CommandKind, Command)> {
@@ -186,7 +223,7 @@ fn check_installed_command(command: String) -> Option<(CommandKind, Command)> {
         }),
         "apt" => (CommandKind::Apt, {
             let mut command = Command::new("apt");
-            command.args(&["list", "-i"]);
+            command.args(["list", "-i"]);
             command
         }),
         "xbps" => (CommandKind::Xbps, {
@@ -206,18 +243,18 @@ fn check_installed_command(command: String) -> Option<(CommandKind, Command)> {
         }),
         "dnf" => (CommandKind::Dnf, {
             let mut command = Command::new("dnf");
-            command.args(&["list", "installed"]);
+            command.args(["list", "installed"]);
             command
         }),
         "nix-user" => (CommandKind::NixUser, {
             let mut command = Command::new("nix-store");
             let user = env::var("USER").unwrap_or_else(|_| "UNKNOWN".to_string());
-            command.args(&["-qR", format!("/home/{}/.nix-profile", user).as_str()]);
+            command.args(["-qR", format!("/home/{}/.nix-profile", user).as_str()]);
             command
         }),
         "nix-system" => (CommandKind::NixSystem, {
             let mut command = Command::new("nix-store");
-            command.args(&["-qR", "/run/current-system/sw"]);
+            command.args(["-qR", "/run/current-system/sw"]);
             command
         }),
         other => {
@@ -231,44 +268,65 @@ fn check_installed_command(command: String) -> Option<(CommandKind, Command)> {
 
 #[tracing::instrument]
 async fn do_installed_counting(arg: String) -> Option<i32> {
-    let (kind, mut command) = check_installed_command(arg)?;
-    let reader = command
+    let (kind, command) = check_installed_command(arg)?;
+    // The child and what it started are killed if this future is dropped because it took too long
+    let mut child = own_group(command)
         .stderr(Stdio::null())
         .stdout(Stdio::piped())
+        .kill_on_drop(true)
         .spawn()
-        .ok()?
-        .stdout
-        .take()?;
+        .ok()?;
+    let group = ProcessGroup::new(&child);
+    let reader = child.stdout.take()?;
 
     let fs = BufReader::new(reader);
-    match kind {
+    let count = match kind {
         CommandKind::Apt => count_lines(2, fs).await,
         _ => count_lines(0, fs).await,
+    };
+    if child.wait().await.is_ok() {
+        group.release();
     }
+
+    count
 }
 
 #[tracing::instrument]
-pub(crate) async fn get_package_count() -> Option<i32> {
-    match &CONF.packages.package_managers {
-        Some(toml::Value::Array(pm)) => {
-            let mut handles = Vec::new();
+pub(crate) async fn get_package_count() -> Option<Packages> {
+    if !CONF.packages.package_count.enabled || !CONF.layout.shows("packages") {
+        return None;
+    }
 
-            for arg in pm {
-                if let toml::Value::String(string) = arg {
-                    let handle = tokio::spawn(do_installed_counting(string.to_owned()));
-                    handles.push(handle);
-                }
-            }
+    cached(
+        "packages",
+        CONF.packages.package_count.cache_ttl,
+        collect_packages,
+    )
+    .await
+}
+
+pub(crate) async fn collect_packages() -> Option<Packages> {
+    let count = match &CONF.packages.package_managers {
+        Some(toml::Value::Array(pm)) => {
+            // Not spawned, so that all commands are killed together if this takes too long
+            let counts = join_all(
+                pm.iter()
+                    .filter_map(toml::Value::as_str)
+                    .map(|string| do_installed_counting(string.to_string())),
+            )
+            .await;
 
             let mut total_packages = 0;
 
-            for handle in handles {
-                total_packages += handle.await.ok()??;
+            for count in counts {
+                total_packages += count?;
             }
 
-            Some(total_packages)
+            total_packages
         }
-        Some(toml::Value::String(pm)) => do_installed_counting(pm.clone()).await,
-        _ => None,
-    }
+        Some(toml::Value::String(pm)) => do_installed_counting(pm.clone()).await?,
+        _ => return None,
+    };
+
+    Some(Packages(count))
 }
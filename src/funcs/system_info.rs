@@ -1,165 +1,720 @@
 use {
-    crate::util::statics::CONF,
-    std::env,
-    substring::Substring,
+    crate::util::{
+        conf_structs::{DiskUsage, MemUsage, Mounts, SwapUsage, UsedMemory},
+        formatting::upper_first,
+        gauge::Gauge,
+        module::{pick_icon, Module},
+        statics::{CONF, LOCALE, MISC_EMOJIS, MISC_ICONS},
+        template::fill,
+    },
+    chrono::{Local, TimeZone},
+    serde::Serialize,
+    std::{collections::HashSet, env, fs, path::Path, thread, time::Duration},
     sys_info::{linux_os_release, os_release},
-    systemstat::{saturating_sub_bytes, Platform, System},
+    systemstat::{saturating_sub_bytes, ByteSize, Filesystem, Platform, System},
+    tokio::time::sleep,
 };
 
-#[tracing::instrument]
-pub(crate) fn get_release_blocking() -> Option<String> {
-    if !CONF.system.release.enabled {
-        return None;
+#[derive(Serialize)]
+pub(crate) struct Release(pub(crate) String);
+
+impl Module for Release {
+    fn name(&self) -> &'static str {
+        "release"
     }
 
-    let rel = linux_os_release().ok()?.pretty_name?; // this performs a blocking read of /etc/os-release
+    fn icon(&self) -> Option<&'static str> {
+        pick_icon(MISC_ICONS[0], MISC_EMOJIS[0])
+    }
 
-    if CONF.icons.enabled {
-        match CONF.icons.kind.as_deref() {
-            Some("emoji") => {
-                if rel.len() > 41 {
-                    Some(format!("{}...", rel.trim_matches('\"').substring(0, 37)))
-                } else {
-                    Some(
-                        rel.trim_matches('\"')
-                            .trim_end_matches('\n')
-                            .trim_end_matches('\"')
-                            .to_string(),
-                    )
-                }
-            }
-            Some(&_) | None => {
-                if rel.len() > 42 {
-                    Some(format!("{}...", rel.trim_matches('\"').substring(0, 38)))
-                } else {
-                    Some(
-                        rel.trim_matches('\"')
-                            .trim_end_matches('\n')
-                            .trim_end_matches('\"')
-                            .to_string(),
-                    )
-                }
-            }
+    fn text(&self) -> String {
+        fill(&CONF.system.release.format, &[("name", self.0.clone())])
+    }
+}
+
+#[derive(Serialize)]
+pub(crate) struct Kernel(pub(crate) String);
+
+impl Module for Kernel {
+    fn name(&self) -> &'static str {
+        "kernel"
+    }
+
+    fn icon(&self) -> Option<&'static str> {
+        pick_icon(MISC_ICONS[1], MISC_EMOJIS[1])
+    }
+
+    fn text(&self) -> String {
+        fill(&CONF.system.kernel.format, &[("version", self.0.clone())])
+    }
+}
+
+/// The share of `total` that is `used`, in percent
+fn percent(used: u64, total: u64) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        used as f64 / total as f64 * 100.0
+    }
+}
+
+/// The placeholders of the memory and disk formats
+fn usage_values(used: u64, total: u64, free: u64) -> [(&'static str, String); 4] {
+    [
+        ("used", ByteSize(used).to_string()),
+        ("free", ByteSize(free).to_string()),
+        ("total", ByteSize(total).to_string()),
+        ("percent", format!("{:.0}", percent(used, total))),
+    ]
+}
+
+impl MemUsage {
+    /// The default formats come from the locale, with used or free space first
+    fn format(&self) -> String {
+        match (&self.format, self.free_before_used) {
+            (Some(format), _) => format.clone(),
+            (None, Some(true)) => LOCALE.get("usage_free_first"),
+            (None, _) => LOCALE.get("usage_used_first"),
+        }
+    }
+}
+
+impl SwapUsage {
+    fn format(&self) -> String {
+        match &self.format {
+            Some(format) => format.clone(),
+            None => LOCALE.get("usage_used_first"),
         }
-    } else if rel.len() > 42 {
-        Some(format!("{}...", rel.trim_matches('\"').substring(0, 38)))
+    }
+}
+
+impl DiskUsage {
+    /// Rows of mounts other than `/` start with the mount point by default
+    fn format(&self) -> String {
+        let root_only = self.mounts == Mounts::Paths(vec!["/".into()]);
+        match (&self.format, self.free_before_used) {
+            (Some(format), _) => format.clone(),
+            (None, _) if !root_only => LOCALE.get("usage_mount"),
+            (None, Some(false)) => LOCALE.get("usage_used_first"),
+            (None, _) => LOCALE.get("usage_free_first"),
+        }
+    }
+}
+
+/// Memory usage in bytes
+#[derive(Serialize)]
+pub(crate) struct Memory {
+    pub(crate) used: u64,
+    pub(crate) total: u64,
+    pub(crate) free: u64,
+}
+
+impl Module for Memory {
+    fn name(&self) -> &'static str {
+        "memory"
+    }
+
+    fn icon(&self) -> Option<&'static str> {
+        pick_icon(MISC_ICONS[2], MISC_EMOJIS[2])
+    }
+
+    fn text(&self) -> String {
+        fill(
+            &CONF.system.mem_usage.format(),
+            &usage_values(self.used, self.total, self.free),
+        )
+    }
+
+    fn gauge(&self) -> Option<Gauge> {
+        let conf = &CONF.system.mem_usage;
+        Some(Gauge {
+            percent: percent(self.used, self.total),
+            bar: conf.bar,
+            warning: conf.warning,
+            critical: conf.critical,
+        })
+    }
+}
+
+/// Swap usage in bytes
+#[derive(Serialize)]
+pub(crate) struct Swap {
+    pub(crate) used: u64,
+    pub(crate) total: u64,
+    pub(crate) free: u64,
+}
+
+impl Module for Swap {
+    fn name(&self) -> &'static str {
+        "swap"
+    }
+
+    fn icon(&self) -> Option<&'static str> {
+        pick_icon(MISC_ICONS[12], MISC_EMOJIS[12])
+    }
+
+    fn text(&self) -> String {
+        fill(
+            &CONF.system.swap.format(),
+            &usage_values(self.used, self.total, self.free),
+        )
+    }
+
+    fn gauge(&self) -> Option<Gauge> {
+        let conf = &CONF.system.swap;
+        Some(Gauge {
+            percent: percent(self.used, self.total),
+            bar: conf.bar,
+            warning: conf.warning,
+            critical: conf.critical,
+        })
+    }
+}
+
+/// Disk usage of a mount in bytes
+#[derive(Serialize)]
+pub(crate) struct Disk {
+    pub(crate) mount: String,
+    pub(crate) used: u64,
+    pub(crate) total: u64,
+    pub(crate) free: u64,
+}
+
+impl Module for Disk {
+    fn name(&self) -> &'static str {
+        "disk"
+    }
+
+    fn icon(&self) -> Option<&'static str> {
+        pick_icon(MISC_ICONS[3], MISC_EMOJIS[3])
+    }
+
+    fn text(&self) -> String {
+        let mut values = usage_values(self.used, self.total, self.free).to_vec();
+        values.push(("mount", self.mount.clone()));
+
+        fill(&CONF.system.disk_usage.format(), &values)
+    }
+
+    fn gauge(&self) -> Option<Gauge> {
+        let conf = &CONF.system.disk_usage;
+        Some(Gauge {
+            percent: percent(self.used, self.total),
+            bar: conf.bar,
+            warning: conf.warning,
+            critical: conf.critical,
+        })
+    }
+}
+
+#[derive(Serialize)]
+pub(crate) struct Environment(pub(crate) String);
+
+impl Module for Environment {
+    fn name(&self) -> &'static str {
+        "environment"
+    }
+
+    fn icon(&self) -> Option<&'static str> {
+        pick_icon(MISC_ICONS[4], MISC_EMOJIS[4])
+    }
+
+    fn text(&self) -> String {
+        fill(
+            &CONF.system.desktop_env.format,
+            &[("name", upper_first(self.0.clone()))],
+        )
+    }
+}
+
+/// Time since boot in seconds
+#[derive(Serialize)]
+pub(crate) struct Uptime {
+    pub(crate) seconds: u64,
+    /// Boot time as a Unix timestamp
+    pub(crate) boot: i64,
+}
+
+/// `seconds` in the two largest units that are not zero, like "3d 4h" or "5m"
+pub(crate) fn humanize(seconds: u64) -> String {
+    let units = [
+        (seconds / 86400, "d"),
+        (seconds / 3600 % 24, "h"),
+        (seconds / 60 % 60, "m"),
+    ];
+
+    let parts: Vec<String> = units
+        .iter()
+        .skip_while(|(value, _)| *value == 0)
+        .take(2)
+        .filter(|(value, _)| *value != 0)
+        .map(|(value, unit)| format!("{}{}", value, unit))
+        .collect();
+
+    if parts.is_empty() {
+        "0m".to_string()
     } else {
-        Some(
-            rel.trim_matches('\"')
-                .trim_end_matches('\n')
-                .trim_end_matches('\"')
-                .to_string(),
+        parts.join(" ")
+    }
+}
+
+impl Module for Uptime {
+    fn name(&self) -> &'static str {
+        "uptime"
+    }
+
+    fn icon(&self) -> Option<&'static str> {
+        pick_icon(MISC_ICONS[7], MISC_EMOJIS[7])
+    }
+
+    fn text(&self) -> String {
+        let boot = Local
+            .timestamp_opt(self.boot, 0)
+            .single()
+            .map(|boot| boot.format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_default();
+
+        fill(
+            &CONF.system.uptime.format,
+            &[
+                ("uptime", humanize(self.seconds)),
+                ("days", (self.seconds / 86400).to_string()),
+                ("hours", (self.seconds / 3600 % 24).to_string()),
+                ("minutes", (self.seconds / 60 % 60).to_string()),
+                ("boot", boot),
+            ],
+        )
+    }
+}
+
+/// Load averages over the last 1, 5 and 15 minutes
+#[derive(Serialize)]
+pub(crate) struct Load {
+    pub(crate) one: f32,
+    pub(crate) five: f32,
+    pub(crate) fifteen: f32,
+}
+
+impl Module for Load {
+    fn name(&self) -> &'static str {
+        "load"
+    }
+
+    fn icon(&self) -> Option<&'static str> {
+        pick_icon(MISC_ICONS[8], MISC_EMOJIS[8])
+    }
+
+    fn text(&self) -> String {
+        fill(
+            &CONF.system.load.format,
+            &[
+                ("one", format!("{:.2}", self.one)),
+                ("five", format!("{:.2}", self.five)),
+                ("fifteen", format!("{:.2}", self.fifteen)),
+            ],
+        )
+    }
+}
+
+/// The number of processes that are running right now, and of all that exist
+#[derive(Serialize)]
+pub(crate) struct Processes {
+    pub(crate) running: u64,
+    pub(crate) total: u64,
+}
+
+impl Module for Processes {
+    fn name(&self) -> &'static str {
+        "processes"
+    }
+
+    fn icon(&self) -> Option<&'static str> {
+        pick_icon(MISC_ICONS[9], MISC_EMOJIS[9])
+    }
+
+    fn text(&self) -> String {
+        match &CONF.system.processes.format {
+            Some(format) => fill(
+                format,
+                &[
+                    ("count", self.running.to_string()),
+                    ("total", self.total.to_string()),
+                ],
+            ),
+            None => LOCALE.count("processes", self.running),
+        }
+    }
+}
+
+/// The processor, with its frequency in MHz if it is known
+#[derive(Serialize)]
+pub(crate) struct Cpu {
+    pub(crate) model: String,
+    pub(crate) cores: usize,
+    pub(crate) threads: usize,
+    pub(crate) frequency: Option<f64>,
+}
+
+impl Module for Cpu {
+    fn name(&self) -> &'static str {
+        "cpu"
+    }
+
+    fn icon(&self) -> Option<&'static str> {
+        pick_icon(MISC_ICONS[10], MISC_EMOJIS[10])
+    }
+
+    fn text(&self) -> String {
+        let frequency = match self.frequency {
+            Some(mhz) if mhz >= 1000.0 => format!("{:.2} GHz", mhz / 1000.0),
+            Some(mhz) => format!("{:.0} MHz", mhz),
+            None => LOCALE.get("unknown"),
+        };
+
+        fill(
+            &CONF.system.cpu.format,
+            &[
+                ("model", self.model.clone()),
+                ("cores", self.cores.to_string()),
+                ("threads", self.threads.to_string()),
+                ("frequency", frequency),
+            ],
+        )
+    }
+}
+
+/// The share of time the processor was busy while it was measured, in percent
+#[derive(Serialize)]
+pub(crate) struct CpuUsage(pub(crate) f32);
+
+impl Module for CpuUsage {
+    fn name(&self) -> &'static str {
+        "cpu_usage"
+    }
+
+    fn icon(&self) -> Option<&'static str> {
+        pick_icon(MISC_ICONS[11], MISC_EMOJIS[11])
+    }
+
+    fn text(&self) -> String {
+        fill(
+            &CONF.system.cpu_usage.format,
+            &[("percent", format!("{:.0}", self.0))],
         )
     }
 }
 
 #[tracing::instrument]
-pub(crate) fn get_kernel_blocking() -> Option<String> {
-    if !CONF.system.kernel.enabled {
+pub(crate) fn get_release_blocking() -> Option<Release> {
+    if !CONF.system.release.enabled || !CONF.layout.shows("release") {
+        return None;
+    }
+
+    let rel = linux_os_release().ok()?.pretty_name?; // this performs a blocking read of /etc/os-release
+
+    Some(Release(
+        rel.trim_matches('\"')
+            .trim_end_matches('\n')
+            .trim_end_matches('\"')
+            .to_string(),
+    ))
+}
+
+#[tracing::instrument]
+pub(crate) fn get_kernel_blocking() -> Option<Kernel> {
+    if !CONF.system.kernel.enabled || !CONF.layout.shows("kernel") {
         return None;
     }
 
     let kernel = os_release().ok()?; // this performs a blocking read of /proc/sys/kernel/osrelease
-    if CONF.icons.enabled {
-        match CONF.icons.kind.as_deref() {
-            Some("emoji") => {
-                if kernel.len() > 41 {
-                    Some(format!("{}...", kernel.substring(0, 37)))
-                } else {
-                    Some(kernel.trim_end_matches('\n').to_string())
-                }
-            }
-            Some(&_) | None => {
-                if kernel.len() > 42 {
-                    Some(format!("{}...", kernel.substring(0, 38)))
-                } else {
-                    Some(kernel.trim_end_matches('\n').to_string())
+
+    Some(Kernel(kernel.trim_end_matches('\n').to_string()))
+}
+
+#[tracing::instrument]
+pub(crate) fn get_memory() -> Option<Memory> {
+    if !CONF.system.mem_usage.enabled || !CONF.layout.shows("memory") {
+        return None;
+    }
+
+    let mem = match System::new().memory() {
+        Ok(mem) => mem,
+        Err(e) => {
+            tracing::warn!("Could not get memory because: {}", e);
+            return None;
+        }
+    };
+
+    // Older kernels have no MemAvailable, systemstat estimates it for them
+    let meminfo = &mem.platform_memory.meminfo;
+    let free = match CONF.system.mem_usage.used {
+        UsedMemory::Available => meminfo.get("MemAvailable").copied().unwrap_or(mem.free),
+        UsedMemory::Free => meminfo.get("MemFree").copied().unwrap_or(mem.free),
+    };
+
+    Some(Memory {
+        used: saturating_sub_bytes(mem.total, free).as_u64(),
+        total: mem.total.as_u64(),
+        free: free.as_u64(),
+    })
+}
+
+#[tracing::instrument]
+pub(crate) fn get_swap() -> Option<Swap> {
+    if !CONF.system.swap.enabled || !CONF.layout.shows("swap") {
+        return None;
+    }
+
+    match System::new().swap() {
+        // Without any swap there is nothing to show
+        Ok(swap) if swap.total.as_u64() == 0 => None,
+        Ok(swap) => Some(Swap {
+            used: saturating_sub_bytes(swap.total, swap.free).as_u64(),
+            total: swap.total.as_u64(),
+            free: swap.free.as_u64(),
+        }),
+        Err(e) => {
+            tracing::warn!("Could not get swap because: {}", e);
+            None
+        }
+    }
+}
+
+/// The mounts picked by `[system.disk_usage] mounts`, in the order they are listed.
+/// Listed mount points that are not mounted are left out.
+fn pick_mounts(mounts: Vec<Filesystem>) -> Vec<Filesystem> {
+    let conf = &CONF.system.disk_usage;
+
+    match &conf.mounts {
+        Mounts::Paths(paths) => paths
+            .iter()
+            .filter_map(|path| {
+                let mount = mounts
+                    .iter()
+                    .rev()
+                    .find(|m| Path::new(&m.fs_mounted_on) == Path::new(path));
+                if mount.is_none() {
+                    tracing::info!("Skipping disk usage of {}, it is not mounted", path);
                 }
-            }
+                mount.cloned()
+            })
+            .collect(),
+        Mounts::All(_) => {
+            let mut sources = HashSet::new();
+            mounts
+                .into_iter()
+                .filter(|m| !conf.exclude_fstypes.contains(&m.fs_type))
+                // Pseudo filesystems have no size
+                .filter(|m| m.total.as_u64() > 0)
+                // Bind mounts would show the same device again
+                .filter(|m| sources.insert(m.fs_mounted_from.clone()))
+                .collect()
         }
-    } else if kernel.len() > 42 {
-        Some(format!("{}...", kernel.substring(0, 38)))
-    } else {
-        Some(kernel.trim_end_matches('\n').to_string())
     }
 }
 
 #[tracing::instrument]
-pub(crate) fn get_memory() -> Option<String> {
-    if !CONF.system.mem_usage.enabled {
+pub(crate) fn get_disk_usage() -> Vec<Disk> {
+    if !CONF.system.disk_usage.enabled || !CONF.layout.shows("disk") {
+        return Vec::new();
+    }
+
+    let mounts = match System::new().mounts() {
+        Ok(mounts) => mounts,
+        Err(e) => {
+            tracing::warn!("Could not get disk usage because: {}", e);
+            return Vec::new();
+        }
+    };
+
+    pick_mounts(mounts)
+        .into_iter()
+        .map(|disk| Disk {
+            mount: disk.fs_mounted_on,
+            used: saturating_sub_bytes(disk.total, disk.free).as_u64(),
+            total: disk.total.as_u64(),
+            free: disk.free.as_u64(),
+        })
+        .collect()
+}
+
+#[tracing::instrument]
+pub(crate) fn get_uptime() -> Option<Uptime> {
+    if !CONF.system.uptime.enabled || !CONF.layout.shows("uptime") {
         return None;
     }
 
-    match System::new().memory() {
-        Ok(mem) => match CONF.system.mem_usage.free_before_used {
-            Some(false) => Some(format!(
-                "{} Used / {} ({} Free)",
-                saturating_sub_bytes(mem.total, mem.free),
-                mem.total,
-                mem.free
-            )),
-            Some(true) => Some(format!(
-                "{} Free / {} ({} Used)",
-                mem.free,
-                mem.total,
-                saturating_sub_bytes(mem.total, mem.free)
-            )),
-            None => Some(format!(
-                "{} Used / {} ({} Free)",
-                saturating_sub_bytes(mem.total, mem.free),
-                mem.total,
-                mem.free
-            )),
-        },
-        Err(x) => panic!("Could not get memory because: {}", x),
+    let system = System::new();
+    match (system.uptime(), system.boot_time()) {
+        (Ok(uptime), Ok(boot)) => Some(Uptime {
+            seconds: uptime.as_secs(),
+            boot: boot.timestamp(),
+        }),
+        (Err(e), _) | (_, Err(e)) => {
+            tracing::warn!("Could not get uptime because: {}", e);
+            None
+        }
     }
 }
 
 #[tracing::instrument]
-pub(crate) fn get_disk_usage() -> Option<String> {
-    if !CONF.system.disk_usage.enabled {
+pub(crate) fn get_load() -> Option<Load> {
+    if !CONF.system.load.enabled || !CONF.layout.shows("load") {
         return None;
     }
 
-    match System::new().mount_at("/") {
-        Ok(disk) => match CONF.system.disk_usage.free_before_used {
-            Some(false) => Some(format!(
-                "{} Used / {} ({} Free)",
-                saturating_sub_bytes(disk.total, disk.free),
-                disk.total,
-                disk.free
-            )),
-            Some(true) => Some(format!(
-                "{} Free / {} ({} Used)",
-                disk.free,
-                disk.total,
-                saturating_sub_bytes(disk.total, disk.free)
-            )),
-            None => Some(format!(
-                "{} Free / {} ({} Used)",
-                disk.free,
-                disk.total,
-                saturating_sub_bytes(disk.total, disk.free)
-            )),
-        },
-        Err(x) => panic!("Could not get disk usage because: {}", x),
+    match System::new().load_average() {
+        Ok(load) => Some(Load {
+            one: load.one,
+            five: load.five,
+            fifteen: load.fifteen,
+        }),
+        Err(e) => {
+            tracing::warn!("Could not get load average because: {}", e);
+            None
+        }
     }
 }
 
 #[tracing::instrument]
-pub(crate) fn get_environment() -> Option<String> {
-    if !CONF.system.desktop_env.enabled {
+pub(crate) fn get_processes() -> Option<Processes> {
+    if !CONF.system.processes.enabled || !CONF.layout.shows("processes") {
+        return None;
+    }
+
+    // The kernel counts the processes that are running or ready to run in /proc/stat
+    let stat = match fs::read_to_string("/proc/stat") {
+        Ok(stat) => stat,
+        Err(e) => {
+            tracing::warn!("Could not count processes because: {}", e);
+            return None;
+        }
+    };
+    let running = stat
+        .lines()
+        .find_map(|line| line.strip_prefix("procs_running "))
+        .and_then(|count| count.trim().parse().ok())?;
+
+    // Every process has a directory named after its PID in /proc
+    let total = fs::read_dir("/proc")
+        .map(|entries| {
+            entries
+                .filter_map(Result::ok)
+                .filter(|e| e.file_name().to_string_lossy().parse::<u32>().is_ok())
+                .count() as u64
+        })
+        .unwrap_or(0);
+
+    Some(Processes { running, total })
+}
+
+/// The value of a `key : value` line of /proc/cpuinfo, if it has that key
+fn cpuinfo_value<'a>(line: &'a str, key: &str) -> Option<&'a str> {
+    let (k, v) = line.split_once(':')?;
+    Some(v.trim()).filter(|_| k.trim() == key)
+}
+
+/// Drops the trademark signs and the base clock from a model name, since the
+/// frequency is shown on its own
+fn clean_model(model: &str) -> String {
+    let model = model.split(" @ ").next().unwrap_or(model);
+    let model = ["(R)", "(r)", "(TM)", "(tm)"]
+        .iter()
+        .fold(model.to_string(), |model, mark| model.replace(mark, ""));
+
+    model.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// The highest current frequency of any core in MHz, as cpufreq reports it
+fn cpufreq_mhz() -> Option<f64> {
+    fs::read_dir("/sys/devices/system/cpu")
+        .ok()?
+        .filter_map(Result::ok)
+        .filter_map(|e| fs::read_to_string(e.path().join("cpufreq/scaling_cur_freq")).ok())
+        .filter_map(|khz| khz.trim().parse::<f64>().ok())
+        .map(|khz| khz / 1000.0)
+        .reduce(f64::max)
+}
+
+#[tracing::instrument]
+pub(crate) fn get_cpu_blocking() -> Option<Cpu> {
+    if !CONF.system.cpu.enabled || !CONF.layout.shows("cpu") {
+        return None;
+    }
+
+    let cpuinfo = fs::read_to_string("/proc/cpuinfo")
+        .map_err(|e| tracing::warn!("Could not read /proc/cpuinfo because: {}", e))
+        .ok()?;
+    let values = |key: &'static str| cpuinfo.lines().filter_map(move |l| cpuinfo_value(l, key));
+
+    // x86 has a model name, other architectures name it in one of the other keys
+    let model = ["model name", "Model", "Hardware", "cpu model", "cpu"]
+        .iter()
+        .find_map(|key| values(key).next())
+        .map(clean_model)
+        .unwrap_or_else(|| LOCALE.get("unknown"));
+
+    let threads = match values("processor")
+        .filter(|v| v.parse::<u32>().is_ok())
+        .count()
+    {
+        0 => thread::available_parallelism().map_or(1, usize::from),
+        threads => threads,
+    };
+    // Cores are told apart by their package and their core id within it
+    let cores: HashSet<(&str, &str)> = values("physical id").zip(values("core id")).collect();
+    let cores = match cores.len() {
+        0 => threads,
+        cores => cores,
+    };
+
+    let mhz: Vec<f64> = values("cpu MHz").filter_map(|v| v.parse().ok()).collect();
+    let frequency = cpufreq_mhz()
+        .or_else(|| Some(mhz.iter().sum::<f64>() / mhz.len() as f64).filter(|_| !mhz.is_empty()));
+
+    Some(Cpu {
+        model,
+        cores,
+        threads,
+        frequency,
+    })
+}
+
+/// Measures the CPU usage over `[system.cpu_usage] interval_ms`, which only
+/// waits on a timer so the other modules are collected in the meantime
+#[tracing::instrument]
+pub(crate) async fn get_cpu_usage() -> Option<CpuUsage> {
+    if !CONF.system.cpu_usage.enabled || !CONF.layout.shows("cpu_usage") {
+        return None;
+    }
+
+    let measurement = System::new()
+        .cpu_load_aggregate()
+        .map_err(|e| tracing::warn!("Could not get CPU usage because: {}", e))
+        .ok()?;
+    sleep(Duration::from_millis(CONF.system.cpu_usage.interval_ms)).await;
+    let load = measurement
+        .done()
+        .map_err(|e| tracing::warn!("Could not get CPU usage because: {}", e))
+        .ok()?;
+
+    Some(CpuUsage((1.0 - load.idle).clamp(0.0, 1.0) * 100.0))
+}
+
+#[tracing::instrument]
+pub(crate) fn get_environment() -> Option<Environment> {
+    if !CONF.system.desktop_env.enabled || !CONF.layout.shows("environment") {
         return None;
     }
 
-    Some(
+    Some(Environment(
         env::var::<String>(ToString::to_string(&"XDG_CURRENT_DESKTOP"))
             .unwrap_or_else(|_| {
-                env::var(&"XDG_SESSION_DESKTOP").unwrap_or_else(|_| {
+                env::var("XDG_SESSION_DESKTOP").unwrap_or_else(|_| {
                     env::var::<String>(ToString::to_string(&"DESKTOP_SESSION"))
-                        .unwrap_or_else(|_| "Unknown".to_string())
+                        .unwrap_or_else(|_| LOCALE.get("unknown"))
                 })
             })
             .replace("none+", ""),
-    )
+    ))
 }
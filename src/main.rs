@@ -4,23 +4,36 @@ pub mod util;
 use {
     crate::{
         funcs::{
+            battery::get_battery,
+            custom::{custom_modules, get_custom, name_conflict},
+            fortune::get_fortune_blocking,
             greet::{get_hostname, greeting},
             misc::{get_datetime, get_song, get_weather},
             pkgs::{count_updates, get_package_count},
             system_info::{
-                get_disk_usage, get_environment, get_kernel_blocking, get_memory,
-                get_release_blocking,
+                get_cpu_blocking, get_cpu_usage, get_disk_usage, get_environment,
+                get_kernel_blocking, get_load, get_memory, get_processes, get_release_blocking,
+                get_swap, get_uptime,
             },
         },
         util::{
-            formatting::{
-                calc_bottom, calc_whitespace, calc_whitespace_song, calc_with_hostname, upper_first,
-            },
-            statics::{CONF, MISC_EMOJIS, MISC_ICONS, PACKAGE_EMOJIS, PACKAGE_ICONS},
+            cache, commands,
+            conf_structs::{OnTimeout, Width},
+            config::Format,
+            formatting::{beside_logo, calc_bottom, calc_top, display_width},
+            module::{Module, MODULE_NAMES},
+            report::Report,
+            statics::{ARGS, CONF},
+            theme::wants_color,
+            timeout::{detached, limit, TimedOut},
         },
     },
     once_cell::sync::Lazy,
-    std::{process::exit, time::Instant},
+    std::{
+        io::{stderr, stdout, ErrorKind, Write},
+        process::exit,
+        time::Instant,
+    },
     tracing_subscriber::{
         fmt::{format::FmtSpan, layer},
         prelude::*,
@@ -28,9 +41,33 @@ use {
     },
 };
 
+fn boxed<M: Module + 'static>(module: Option<M>) -> Option<Box<dyn Module>> {
+    module.map(|m| Box::new(m) as Box<dyn Module>)
+}
+
+fn finished<T>(result: Result<Option<T>, TimedOut>, timed_out: &mut Vec<TimedOut>) -> Option<T> {
+    result.unwrap_or_else(|t| {
+        timed_out.push(t);
+        None
+    })
+}
+
 #[tokio::main]
 async fn main() {
-    if CONF.util.width < 50 {
+    if let Some(command) = &ARGS.command {
+        commands::run(command);
+    }
+
+    if let Some(module) = &ARGS.refresh {
+        cache::refresh(module).await;
+        return;
+    }
+
+    let too_narrow = match CONF.util.width {
+        Width::Columns(columns) => columns < 50,
+        Width::Auto(_) => CONF.util.min_width < 50,
+    };
+    if too_narrow {
         eprintln!("Width attribute must be at least 50, please change it.");
         exit(1);
     }
@@ -39,6 +76,7 @@ async fn main() {
         .with(
             layer()
                 .pretty()
+                .with_ansi(wants_color(&stderr()))
                 .with_span_events(FmtSpan::NEW | FmtSpan::CLOSE),
         )
         .with(EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("warn")))
@@ -46,6 +84,19 @@ async fn main() {
 
     tracing::info!("Running");
 
+    for name in &CONF.layout.modules {
+        if !MODULE_NAMES.contains(&name.as_str()) && !CONF.custom.iter().any(|c| &c.name == name) {
+            tracing::warn!("Unknown module in layout: {}", name);
+        }
+    }
+    let names: Vec<&str> = CONF.custom.iter().map(|c| c.name.as_str()).collect();
+    for (i, name) in names.iter().enumerate() {
+        if let Some(reason) = name_conflict(&names, i) {
+            tracing::warn!("Custom module {} {}, skipping it", name, reason);
+        }
+    }
+    let customs = custom_modules();
+
     let time = Instant::now();
 
     Lazy::force(&CONF);
@@ -55,262 +106,183 @@ async fn main() {
     let greeting = greeting();
     let datetime = get_datetime();
     let memory = get_memory();
+    let swap = get_swap();
     let disk = get_disk_usage();
     let environment = get_environment();
+    let uptime = get_uptime();
+    let load = get_load();
+    let processes = get_processes();
+    let battery = get_battery();
 
-    // These are proper async functions
-    let weather = tokio::spawn(get_weather());
-    let up_count = tokio::spawn(count_updates());
-    let package_count = tokio::spawn(get_package_count());
+    // These are proper async functions, which may be slow
+    let weather = tokio::spawn(limit(
+        "weather",
+        CONF.util.timeout_ms(CONF.weather.timeout_ms),
+        get_weather(),
+    ));
+    let up_count = tokio::spawn(limit(
+        "updates",
+        CONF.util.timeout_ms(CONF.packages.update_count.timeout_ms),
+        count_updates(),
+    ));
+    let package_count = tokio::spawn(limit(
+        "packages",
+        CONF.util.timeout_ms(CONF.packages.package_count.timeout_ms),
+        get_package_count(),
+    ));
+    let cpu_usage = tokio::spawn(limit(
+        "cpu_usage",
+        CONF.util.timeout_ms(CONF.system.cpu_usage.timeout_ms),
+        get_cpu_usage(),
+    ));
+
+    let custom: Vec<_> = customs
+        .iter()
+        .map(|c| {
+            tokio::spawn(limit(
+                &c.name,
+                CONF.util.timeout_ms(c.timeout_ms),
+                get_custom(c),
+            ))
+        })
+        .collect();
+
+    // The song blocks on the media player, which may hang
+    let song = tokio::spawn(limit(
+        "song",
+        CONF.util.timeout_ms(CONF.song.timeout_ms),
+        detached(get_song),
+    ));
 
     // These are functions that block
-    let song = tokio::task::spawn_blocking(get_song);
     let release = tokio::task::spawn_blocking(get_release_blocking);
     let kernel = tokio::task::spawn_blocking(get_kernel_blocking);
+    let cpu = tokio::task::spawn_blocking(get_cpu_blocking);
+    let fortune = tokio::task::spawn_blocking(get_fortune_blocking);
 
-    let weather = weather.await.unwrap();
-    let up_count = up_count.await.unwrap();
-    let package_count = package_count.await.unwrap();
+    let mut timed_out = Vec::new();
+    let weather = finished(weather.await.unwrap(), &mut timed_out);
+    let up_count = finished(up_count.await.unwrap(), &mut timed_out);
+    let package_count = finished(package_count.await.unwrap(), &mut timed_out);
+    let cpu_usage = finished(cpu_usage.await.unwrap(), &mut timed_out);
+    let song = finished(song.await.unwrap(), &mut timed_out);
+    let mut custom_rows = Vec::new();
+    for handle in custom {
+        custom_rows.extend(finished(handle.await.unwrap(), &mut timed_out));
+    }
 
-    let song = song.await.unwrap();
     let release = release.await.unwrap();
     let kernel = kernel.await.unwrap();
+    let cpu = cpu.await.unwrap();
+    let fortune = fortune.await.unwrap();
 
     tracing::info!(
         "Finished collecting data in {:.3}",
         time.elapsed().as_secs_f32()
     );
 
-    if let Some(hostname) = hostname {
-        println!(
-            "{}",
-            calc_with_hostname(format!("╭─── \x1b[32m{}\x1b[0m ", hostname))
-        );
-    }
-
-    if let Some(greeting) = greeting {
-        println!("{}", calc_whitespace(format!("│ {}!", greeting)));
+    if ARGS.format != Format::Box {
+        Report {
+            hostname: &hostname,
+            greeting: &greeting,
+            datetime: &datetime,
+            weather: &weather,
+            release: &release,
+            kernel: &kernel,
+            memory: &memory,
+            swap: &swap,
+            disk: &disk,
+            environment: &environment,
+            uptime: &uptime,
+            load: &load,
+            processes: &processes,
+            cpu: &cpu,
+            cpu_usage: &cpu_usage,
+            battery: &battery,
+            updates: &up_count,
+            packages: &package_count,
+            song: &song,
+            fortune: &fortune,
+            custom: &custom_rows,
+        }
+        .print(ARGS.format);
+        return;
     }
 
-    if let Some(datetime) = datetime {
-        println!("{}", calc_whitespace(datetime));
-    }
+    let mut modules: Vec<Box<dyn Module>> = [
+        boxed(greeting),
+        boxed(datetime),
+        boxed(weather),
+        boxed(release),
+        boxed(kernel),
+        boxed(memory),
+        boxed(swap),
+        boxed(environment),
+        boxed(uptime),
+        boxed(load),
+        boxed(processes),
+        boxed(cpu),
+        boxed(cpu_usage),
+        boxed(battery),
+        boxed(up_count),
+        boxed(package_count),
+        boxed(song),
+        boxed(fortune),
+    ]
+    .into_iter()
+    .flatten()
+    .chain(disk.into_iter().map(|d| Box::new(d) as Box<dyn Module>))
+    .chain(
+        custom_rows
+            .into_iter()
+            .map(|c| Box::new(c) as Box<dyn Module>),
+    )
+    .collect();
 
-    if let Some(weather) = weather {
-        println!("{}", calc_whitespace(weather));
+    if CONF.util.on_timeout == OnTimeout::Placeholder {
+        modules.extend(
+            timed_out
+                .into_iter()
+                .map(|t| Box::new(t) as Box<dyn Module>),
+        );
     }
 
-    if CONF.icons.enabled {
-        match CONF.icons.kind.as_deref() {
-            Some("emoji") => {
-                if let Some(release) = release {
-                    println!(
-                        "{}",
-                        calc_whitespace(format!("│ {} {}", MISC_EMOJIS[0], release))
-                    );
-                }
-                if let Some(kernel) = kernel {
-                    println!(
-                        "{}",
-                        calc_whitespace(format!("│ {} {}", MISC_EMOJIS[1], kernel))
-                    );
-                }
-                if let Some(memory) = memory {
-                    println!(
-                        "{}",
-                        calc_whitespace(format!("│ {} {}", MISC_EMOJIS[2], memory))
-                    );
-                }
-                if let Some(disk) = disk {
-                    println!(
-                        "{}",
-                        calc_whitespace(format!("│ {} {}", MISC_EMOJIS[3], disk))
-                    );
-                }
-
-                if let Some(environment) = environment {
-                    println!(
-                        "{}",
-                        calc_whitespace(format!(
-                            "│ {} {}",
-                            MISC_EMOJIS[4],
-                            upper_first(environment)
-                        ))
-                    );
-                }
-            }
-            Some("normal") => {
-                if let Some(release) = release {
-                    println!(
-                        "{}",
-                        calc_whitespace(format!("│ {} {}", MISC_ICONS[0], release))
-                    );
-                }
-                if let Some(kernel) = kernel {
-                    println!(
-                        "{}",
-                        calc_whitespace(format!("│ {} {}", MISC_ICONS[1], kernel))
-                    );
-                }
-                if let Some(memory) = memory {
-                    println!(
-                        "{}",
-                        calc_whitespace(format!("│ {} {}", MISC_ICONS[2], memory))
-                    );
-                }
-                if let Some(disk) = disk {
-                    println!(
-                        "{}",
-                        calc_whitespace(format!("│ {} {}", MISC_ICONS[3], disk))
-                    );
-                }
-
-                if let Some(environment) = environment {
-                    println!(
-                        "{}",
-                        calc_whitespace(format!(
-                            "│ {} {}",
-                            MISC_ICONS[4],
-                            upper_first(environment)
-                        ))
-                    );
-                }
-            }
-            Some(&_) | None => {
-                if let Some(release) = release {
-                    println!("{}", calc_whitespace(format!("│ {}", release)));
-                }
-                if let Some(kernel) = kernel {
-                    println!("{}", calc_whitespace(format!("│ {}", kernel)));
-                }
-                if let Some(memory) = memory {
-                    println!("{}", calc_whitespace(format!("│ {}", memory)));
-                }
-                if let Some(disk) = disk {
-                    println!("{}", calc_whitespace(format!("│ {}", disk)));
-                }
-                if let Some(environment) = environment {
-                    println!(
-                        "{}",
-                        calc_whitespace(format!("│ {}", upper_first(environment)))
-                    );
-                }
-            }
-        }
-    } else {
-        if let Some(release) = release {
-            println!("{}", calc_whitespace(format!("│ {}", release)));
-        }
-        if let Some(kernel) = kernel {
-            println!("{}", calc_whitespace(format!("│ {}", kernel)));
-        }
-        if let Some(memory) = memory {
-            println!("{}", calc_whitespace(format!("│ {}", memory)));
-        }
-        if let Some(disk) = disk {
-            println!("{}", calc_whitespace(format!("│ {}", disk)));
-        }
-
-        if let Some(environment) = environment {
-            println!(
-                "{}",
-                calc_whitespace(format!("│ {}", upper_first(environment)))
-            );
-        }
-    }
+    // The hostname is the title of the box, so its position in the layout does not matter
+    let mut lines = match hostname {
+        Some(hostname) => hostname.render(0),
+        None => vec![calc_top(None)],
+    };
 
-    if let Some(count) = up_count {
-        println!("{}", calc_whitespace(count));
-    }
+    // Labels are padded to the longest one shown, so the values line up
+    let label_width = modules
+        .iter()
+        .map(|m| display_width(&m.label()))
+        .max()
+        .unwrap_or(0);
 
-    if CONF.icons.enabled {
-        match CONF.icons.kind.as_deref() {
-            Some("emoji") => match package_count {
-                None => (),
-                Some(0) => println!(
-                    "{}",
-                    calc_whitespace(format!("│ {} No packages", PACKAGE_EMOJIS[12]))
-                ),
-                Some(1) => println!(
-                    "{}",
-                    calc_whitespace(format!("│ {} 1 package", PACKAGE_EMOJIS[12]))
-                ),
-                Some(n) => println!(
-                    "{}",
-                    calc_whitespace(format!("│ {} {} packages", PACKAGE_EMOJIS[12], n))
-                ),
-            },
-            Some("normal") => match package_count {
-                None => (),
-                Some(0) => println!(
-                    "{}",
-                    calc_whitespace(format!("│ {} No packages", PACKAGE_ICONS[11]))
-                ),
-                Some(1) => println!(
-                    "{}",
-                    calc_whitespace(format!("│ {} 1 package", PACKAGE_ICONS[11]))
-                ),
-                Some(n) => println!(
-                    "{}",
-                    calc_whitespace(format!("│ {} {} packages", PACKAGE_ICONS[11], n))
-                ),
-            },
-            Some(&_) | None => match package_count {
-                None => (),
-                Some(0) => println!("{}", calc_whitespace("│ No packages".to_string())),
-                Some(1) => println!("{}", calc_whitespace("│ 1 package".to_string())),
-                Some(n) => println!("{}", calc_whitespace(format!("│ {} packages", n))),
-            },
-        }
-    } else {
-        match package_count {
-            None => (),
-            Some(0) => println!("{}", calc_whitespace("│ No packages".to_string())),
-            Some(1) => println!("{}", calc_whitespace("│ 1 package".to_string())),
-            Some(n) => println!("{}", calc_whitespace(format!("│ {} packages", n))),
+    // Custom modules missing from the layout are shown at the end
+    let unlisted = customs
+        .iter()
+        .map(|c| &c.name)
+        .filter(|name| !CONF.layout.shows(name));
+    for name in CONF.layout.modules.iter().chain(unlisted) {
+        // Some modules, like the disk usage, have a row for each thing they show
+        for module in modules.iter().filter(|m| m.name() == name) {
+            lines.extend(module.render(label_width));
         }
     }
 
-    if CONF.icons.enabled {
-        match CONF.icons.kind.as_deref() {
-            Some("emoji") => {
-                if let Some(song) = song.as_ref() {
-                    println!(
-                        "{}",
-                        calc_whitespace_song(format!(
-                            "│ {} {}",
-                            MISC_EMOJIS[5],
-                            song.trim_matches('\n')
-                        ))
-                    );
-                }
-            }
-            Some("normal") => {
-                if let Some(song) = song.as_ref() {
-                    println!(
-                        "{}",
-                        calc_whitespace_song(format!(
-                            "│ {} {}",
-                            MISC_ICONS[5],
-                            song.trim_matches('\n')
-                        ))
-                    );
-                }
-            }
-            Some(&_) | None => {
-                if let Some(song) = song.as_ref() {
-                    println!(
-                        "{}",
-                        calc_whitespace_song(format!("│ {}", song.trim_matches('\n')))
-                    );
-                }
+    lines.push(calc_bottom());
+    let mut out = stdout().lock();
+    for line in beside_logo(lines) {
+        // Like `--format`, a reader that stops early, as `| head` does, is fine
+        match writeln!(out, "{}", line) {
+            Err(e) if e.kind() == ErrorKind::BrokenPipe => return,
+            Err(e) => {
+                tracing::error!("Could not write output: {}", e);
+                return;
             }
+            Ok(()) => (),
         }
-    } else if let Some(song) = song.as_ref() {
-        println!(
-            "{}",
-            calc_whitespace_song(format!("│ {}", song.trim_matches('\n')))
-        );
     }
-    println!("{}", calc_bottom("╰".into()));
 }
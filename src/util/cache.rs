@@ -0,0 +1,264 @@
+use {
+    crate::{
+        funcs::{
+            custom::{custom_modules, run_custom},
+            misc::fetch_weather,
+            pkgs::{collect_packages, collect_updates},
+        },
+        util::{
+            statics::{ARGS, CONF},
+            timeout::limit,
+        },
+    },
+    serde::{de::DeserializeOwned, Deserialize, Serialize},
+    std::{
+        env, fs,
+        future::Future,
+        path::PathBuf,
+        process::{Command, Stdio},
+        time::{SystemTime, UNIX_EPOCH},
+    },
+};
+
+/// A cached module value and when it was collected
+#[derive(Serialize, Deserialize)]
+struct Entry<T> {
+    time: u64,
+    value: T,
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// `$XDG_CACHE_HOME/draconis`, or `~/.cache/draconis`
+fn cache_dir() -> Option<PathBuf> {
+    let base = env::var_os("XDG_CACHE_HOME")
+        .filter(|p| !p.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| {
+            env::var_os("HOME")
+                .filter(|p| !p.is_empty())
+                .map(|home| PathBuf::from(home).join(".cache"))
+        })?;
+
+    Some(base.join("draconis"))
+}
+
+/// The name of the cache file of `module`. Names of custom modules come from the
+/// config, so anything but letters, digits, `_` and `-` is replaced, and a hash of
+/// the name keeps apart modules that end up the same.
+fn file_name(module: &str) -> String {
+    let safe: String = module
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if safe == module {
+        return format!("{}.json", module);
+    }
+
+    // FNV-1a, which unlike the std hasher stays the same between Rust versions
+    let hash = module.bytes().fold(0xcbf29ce484222325u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    });
+    format!("{}-{:016x}.json", safe, hash)
+}
+
+fn cache_file(module: &str) -> Option<PathBuf> {
+    Some(cache_dir()?.join(file_name(module)))
+}
+
+/// Reads the cached value of `module` along with its age in seconds
+fn read<T: DeserializeOwned>(module: &str) -> Option<(u64, T)> {
+    let content = fs::read_to_string(cache_file(module)?).ok()?;
+    let entry: Entry<T> = serde_json::from_str(&content)
+        .map_err(|e| tracing::info!("Ignoring unreadable cache for {}: {}", module, e))
+        .ok()?;
+
+    Some((now().saturating_sub(entry.time), entry.value))
+}
+
+/// A value read from the cache
+enum Lookup<T> {
+    Fresh(T),
+    /// Older than the TTL, but still usable while a new value is collected
+    Stale(T),
+    Missing,
+}
+
+/// Looks up the cached value of `module`, which is fresh for `ttl` seconds
+fn lookup<T: DeserializeOwned>(module: &str, ttl: u64) -> Lookup<T> {
+    match read(module) {
+        Some((age, value)) if age < ttl => Lookup::Fresh(value),
+        Some((_, value)) => Lookup::Stale(value),
+        None => Lookup::Missing,
+    }
+}
+
+/// Stores `value` as the cached value of `module`
+pub(crate) fn write<T: Serialize>(module: &str, value: &T) {
+    let path = match cache_file(module) {
+        Some(path) => path,
+        None => return,
+    };
+    let entry = Entry { time: now(), value };
+
+    let result = path
+        .parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .and_then(|_| fs::write(&path, serde_json::to_string(&entry)?));
+    if let Err(e) = result {
+        tracing::warn!("Could not write cache file {}: {}", path.display(), e);
+    }
+}
+
+/// Starts a detached `draconis --refresh <module>`, which updates the cache for next time
+fn spawn_refresh(module: &str) {
+    let exe = match env::current_exe() {
+        Ok(exe) => exe,
+        Err(e) => {
+            tracing::warn!(
+                "Could not find the draconis binary to refresh {}: {}",
+                module,
+                e
+            );
+            return;
+        }
+    };
+
+    let mut command = Command::new(exe);
+    if let Some(config) = &ARGS.config {
+        command.args(["--config", config]);
+    }
+    command
+        .args(["--refresh", module])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+
+    if let Err(e) = command.spawn() {
+        tracing::warn!("Could not refresh {} in the background: {}", module, e);
+    }
+}
+
+/// Returns the cached value of `module` if it is younger than `ttl` seconds,
+/// otherwise collects a new one and caches it. With `[util] background_refresh`,
+/// an outdated value is returned as is and refreshed by a background process.
+/// A `ttl` of 0 disables caching.
+pub(crate) async fn cached<T, F>(module: &str, ttl: u64, collect: impl FnOnce() -> F) -> Option<T>
+where
+    T: Serialize + DeserializeOwned,
+    F: Future<Output = Option<T>>,
+{
+    if ttl == 0 {
+        return collect().await;
+    }
+
+    match lookup(module, ttl) {
+        Lookup::Fresh(value) => return Some(value),
+        Lookup::Stale(value) if CONF.util.background_refresh => {
+            spawn_refresh(module);
+            return Some(value);
+        }
+        _ => (),
+    }
+
+    let value = collect().await;
+    if let Some(value) = &value {
+        write(module, value);
+    }
+
+    value
+}
+
+/// Collects `module` without looking at its cache and stores the result
+pub(crate) async fn refresh(module: &str) {
+    match module {
+        "updates" => {
+            if let Some(updates) = collect_updates().await {
+                write(module, &updates);
+            }
+        }
+        "packages" => {
+            if let Some(packages) = collect_packages().await {
+                write(module, &packages);
+            }
+        }
+        "weather" => {
+            // Without a network the request never returns, so it keeps its timeout here too
+            let timeout_ms = CONF.util.timeout_ms(CONF.weather.timeout_ms);
+            if let Ok(Some(weather)) = limit("weather", timeout_ms, fetch_weather()).await {
+                write(module, &weather);
+            }
+        }
+        other => {
+            let custom = other
+                .strip_prefix("custom-")
+                .and_then(|name| custom_modules().into_iter().find(|c| c.name == name));
+            match custom {
+                Some(custom) => {
+                    if let Some(text) = run_custom(custom).await {
+                        write(module, &text);
+                    }
+                }
+                None => eprintln!("Module {} is not cached", other),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, std::path::Path};
+
+    #[test]
+    fn plain_names_are_kept() {
+        assert_eq!(file_name("updates"), "updates.json");
+        assert_eq!(file_name("custom-git_branch"), "custom-git_branch.json");
+    }
+
+    #[test]
+    fn paths_stay_in_the_cache_dir() {
+        for module in ["custom-../../evil", "custom-/etc/passwd", "custom-a b"] {
+            let name = file_name(module);
+            assert!(!name.contains('/') && !name.contains(".."), "{}", name);
+            assert_eq!(Path::new(&name).components().count(), 1);
+        }
+        assert_ne!(file_name("custom-a/b"), file_name("custom-a_b"));
+        assert_ne!(file_name("custom-a/b"), file_name("custom-a.b"));
+    }
+
+    #[test]
+    fn entries_expire_after_their_ttl() {
+        // No other test uses the cache dir, so changing it here is safe
+        let dir = env::temp_dir().join(format!("draconis-cache-{}", std::process::id()));
+        env::set_var("XDG_CACHE_HOME", &dir);
+
+        assert!(matches!(lookup::<u32>("ttl-test", 60), Lookup::Missing));
+
+        write("ttl-test", &7);
+        assert!(matches!(lookup("ttl-test", 60), Lookup::Fresh(7)));
+        // An entry is only fresh while younger than the TTL
+        assert!(matches!(lookup("ttl-test", 0), Lookup::Stale(7)));
+
+        let path = cache_file("ttl-test").unwrap();
+        let old = Entry {
+            time: now() - 120,
+            value: 8,
+        };
+        fs::write(&path, serde_json::to_string(&old).unwrap()).unwrap();
+        assert!(matches!(lookup("ttl-test", 60), Lookup::Stale(8)));
+        assert!(matches!(lookup("ttl-test", 300), Lookup::Fresh(8)));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,312 @@
+use {
+    crate::{
+        funcs::custom::name_conflict,
+        util::{
+            conf_structs::{Config, CustomModule, GreetingEvent},
+            config::{config_paths, load_file, user_config_path, Command},
+            statics::CONF,
+        },
+    },
+    std::{
+        fs,
+        io::{stdout, ErrorKind, Write},
+        path::PathBuf,
+        process::exit,
+    },
+};
+
+/// The commented config written by `draconis config init`
+static DEFAULT_CONFIG: &str = include_str!("default_config.toml");
+
+pub(crate) fn run(command: &Command) -> ! {
+    let code = match command {
+        Command::Init { force } => init(*force),
+        Command::Check => check(),
+        Command::Dump => dump(),
+    };
+
+    exit(code)
+}
+
+fn init(force: bool) -> i32 {
+    let path = match user_config_path() {
+        Some(path) => path,
+        None => {
+            eprintln!("Could not find a place for the config, please set $HOME, $XDG_CONFIG_HOME or $DRACONIS_CONFIG");
+            return 1;
+        }
+    };
+    if path.exists() && !force {
+        eprintln!(
+            "{} already exists, use `draconis config init --force` to overwrite it",
+            path.display()
+        );
+        return 1;
+    }
+
+    if let Some(parent) = path.parent() {
+        if let Err(e) = fs::create_dir_all(parent) {
+            eprintln!("Could not create {}: {}", parent.display(), e);
+            return 1;
+        }
+    }
+
+    match fs::write(&path, DEFAULT_CONFIG) {
+        Ok(()) => {
+            println!("Wrote the default config to {}", path.display());
+            0
+        }
+        Err(e) => {
+            eprintln!("Could not write {}: {}", path.display(), e);
+            1
+        }
+    }
+}
+
+fn check() -> i32 {
+    let paths: Vec<PathBuf> = config_paths().into_iter().filter(|p| p.exists()).collect();
+    if paths.is_empty() {
+        println!("No config file found, the default config is used");
+        return 0;
+    }
+
+    let known = known_keys();
+    let mut code = 0;
+    for path in &paths {
+        let user = match load_file(path) {
+            Ok(Some(user)) => user,
+            Ok(None) => {
+                println!("{} is empty", path.display());
+                continue;
+            }
+            Err(e) => {
+                eprintln!("{}", e);
+                code = 1;
+                continue;
+            }
+        };
+
+        let mut unknown = Vec::new();
+        unknown_keys(&user, &known, "", &mut unknown);
+        let conflicts = custom_name_conflicts(&user);
+
+        if unknown.is_empty() && conflicts.is_empty() {
+            println!("{} is valid", path.display());
+            continue;
+        }
+
+        if !unknown.is_empty() {
+            eprintln!(
+                "{} contains keys Draconis does not know about:",
+                path.display()
+            );
+        }
+        for (key, suggestion) in &unknown {
+            match suggestion {
+                Some(suggestion) => eprintln!("  `{}`, did you mean `{}`?", key, suggestion),
+                None => eprintln!("  `{}`", key),
+            }
+        }
+        if !conflicts.is_empty() {
+            eprintln!(
+                "{} contains custom modules that are skipped:",
+                path.display()
+            );
+        }
+        for (name, reason) in &conflicts {
+            eprintln!("  `{}` {}", name, reason);
+        }
+        code = 1;
+    }
+
+    code
+}
+
+/// The `[[custom]]` modules of a config file that are skipped for their name
+fn custom_name_conflicts(user: &toml::Value) -> Vec<(&str, &'static str)> {
+    let names: Vec<&str> = user
+        .get("custom")
+        .and_then(toml::Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|custom| custom.get("name")?.as_str())
+        .collect();
+
+    (0..names.len())
+        .filter_map(|i| Some((names[i], name_conflict(&names, i)?)))
+        .collect()
+}
+
+fn dump() -> i32 {
+    // Going through `toml::Value` puts plain values before tables, which TOML requires
+    match toml::Value::try_from(&*CONF).and_then(|value| toml::to_string(&value)) {
+        // Readers like `| head` may stop early, which is not an error
+        Ok(config) => match write!(stdout().lock(), "{}", config) {
+            Ok(()) => 0,
+            Err(e) if e.kind() == ErrorKind::BrokenPipe => 0,
+            Err(e) => {
+                eprintln!("Could not write the config: {}", e);
+                1
+            }
+        },
+        Err(e) => {
+            eprintln!("Could not serialize the config: {}", e);
+            1
+        }
+    }
+}
+
+/// The default config serialized to JSON rather than TOML, so that unset optional
+/// keys still show up as `null`. Arrays of tables are empty by default, so they get
+/// a default element here for `unknown_keys` to check every table against.
+fn known_keys() -> serde_json::Value {
+    let element = |value: serde_json::Result<serde_json::Value>| {
+        serde_json::Value::Array(vec![value.expect("default config serializes")])
+    };
+
+    let mut known = serde_json::to_value(Config::default()).expect("default config serializes");
+    known["custom"] = element(serde_json::to_value(CustomModule::default()));
+    known["greeting"]["events"] = element(serde_json::to_value(GreetingEvent::default()));
+
+    known
+}
+
+/// Collects every key in `user` that has no counterpart in `known`, along with
+/// the closest known key if it looks like a typo.
+///
+/// Empty tables in `known` are maps without fixed keys, their contents are not
+/// checked. Every element of an array is checked against the first one in `known`.
+fn unknown_keys(
+    user: &toml::Value,
+    known: &serde_json::Value,
+    prefix: &str,
+    out: &mut Vec<(String, Option<String>)>,
+) {
+    if let (toml::Value::Array(user), serde_json::Value::Array(known)) = (user, known) {
+        if let Some(element) = known.first() {
+            for (i, value) in user.iter().enumerate() {
+                unknown_keys(value, element, &format!("{}[{}]", prefix, i), out);
+            }
+        }
+        return;
+    }
+
+    let (user, known) = match (user, known) {
+        (toml::Value::Table(user), serde_json::Value::Object(known)) if !known.is_empty() => {
+            (user, known)
+        }
+        _ => return,
+    };
+
+    for (key, value) in user {
+        let path = if prefix.is_empty() {
+            key.clone()
+        } else {
+            format!("{}.{}", prefix, key)
+        };
+
+        match known.get(key) {
+            Some(known) => unknown_keys(value, known, &path, out),
+            None => {
+                let suggestion = known
+                    .keys()
+                    .map(|k| (edit_distance(key, k), k))
+                    .filter(|(distance, _)| *distance <= 2)
+                    .min()
+                    .map(|(_, k)| k.clone());
+                out.push((path, suggestion));
+            }
+        }
+    }
+}
+
+/// The Levenshtein distance between two strings
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut previous = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let current = row[j + 1];
+            row[j + 1] = if ca == *cb {
+                previous
+            } else {
+                1 + previous.min(row[j]).min(current)
+            };
+            previous = current;
+        }
+    }
+
+    row[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unknown(config: &str) -> Vec<(String, Option<String>)> {
+        let mut out = Vec::new();
+        unknown_keys(
+            &toml::from_str(config).unwrap(),
+            &known_keys(),
+            "",
+            &mut out,
+        );
+        out
+    }
+
+    fn typo(key: &str, suggestion: &str) -> (String, Option<String>) {
+        (key.to_string(), Some(suggestion.to_string()))
+    }
+
+    #[test]
+    fn known_keys_pass() {
+        let config = "[util]\nwidth = 60\n\n[system.mem_usage]\nenabled = true\n";
+        assert!(unknown(config).is_empty());
+        assert!(unknown(DEFAULT_CONFIG).is_empty());
+    }
+
+    #[test]
+    fn typos_get_suggestions() {
+        let config = "[util]\nwdith = 60\n\n[system.mem_usage]\nenabld = true\nzzz = 1\n";
+        assert_eq!(
+            unknown(config),
+            [
+                typo("system.mem_usage.enabld", "enabled"),
+                ("system.mem_usage.zzz".to_string(), None),
+                typo("util.wdith", "width"),
+            ]
+        );
+    }
+
+    #[test]
+    fn free_form_tables_are_not_checked() {
+        assert!(unknown("[layout.labels]\nanything = \"x\"\n").is_empty());
+    }
+
+    #[test]
+    fn arrays_of_tables_are_checked() {
+        let config = "[[custom]]\nname = \"a\"\ncommand = \"x\"\n\n[[custom]]\nname = \"b\"\ncomand = \"y\"\ntimout_ms = 5\n\n[[greeting.events]]\ndate = \"01-01\"\nmesage = \"Hi\"\n";
+        assert_eq!(
+            unknown(config),
+            [
+                typo("custom[1].comand", "command"),
+                typo("custom[1].timout_ms", "timeout_ms"),
+                typo("greeting.events[0].mesage", "message"),
+            ]
+        );
+    }
+
+    #[test]
+    fn taken_custom_names_are_reported() {
+        let config = "[[custom]]\nname = \"vpn\"\ncommand = \"x\"\n\n[[custom]]\nname = \"memory\"\ncommand = \"y\"\n\n[[custom]]\nname = \"vpn\"\ncommand = \"z\"\n";
+        let user = config.parse().unwrap();
+        let names: Vec<&str> = custom_name_conflicts(&user)
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect();
+        assert_eq!(names, ["memory", "vpn"]);
+    }
+}
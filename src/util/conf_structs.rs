@@ -1,115 +1,832 @@
-use serde::Deserialize;
+use {
+    crate::util::module::MODULE_NAMES,
+    serde::{Deserialize, Serialize},
+    std::collections::BTreeMap,
+};
 
-#[derive(Deserialize)]
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
 pub(crate) struct Config {
     pub(crate) main: Main,
     pub(crate) util: Util,
     pub(crate) greeting: Greeting,
     pub(crate) icons: Icons,
+    pub(crate) locale: Locale,
+    pub(crate) theme: Theme,
+    pub(crate) logo: Logo,
     pub(crate) time: Time,
     pub(crate) weather: Weather,
     pub(crate) system: System,
     pub(crate) packages: Packages,
     pub(crate) song: Song,
+    pub(crate) fortune: Fortune,
+    pub(crate) battery: Battery,
+    pub(crate) layout: Layout,
+    pub(crate) custom: Vec<CustomModule>,
 }
 
-#[derive(Deserialize)]
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
 pub(crate) struct Main {
     pub(crate) hostname: Option<String>,
     pub(crate) name: Option<String>,
 }
 
-#[derive(Deserialize)]
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
 pub(crate) struct Util {
-    pub(crate) width: i32,
+    pub(crate) width: Width,
+    /// Bounds for `width = "auto"`
+    pub(crate) min_width: usize,
+    pub(crate) max_width: usize,
+    /// How long slow modules may take before giving up on them, 0 waits forever
+    pub(crate) timeout_ms: u64,
+    pub(crate) on_timeout: OnTimeout,
+    /// Return outdated cached values right away and refresh them in the background
+    pub(crate) background_refresh: bool,
 }
 
-#[derive(Deserialize)]
+impl Default for Util {
+    fn default() -> Self {
+        Self {
+            width: Width::Columns(55),
+            min_width: 50,
+            max_width: 80,
+            timeout_ms: 2000,
+            on_timeout: OnTimeout::Placeholder,
+            background_refresh: false,
+        }
+    }
+}
+
+impl Util {
+    /// The timeout of a module, falling back to the global one
+    pub(crate) fn timeout_ms(&self, module: Option<u64>) -> u64 {
+        module.unwrap_or(self.timeout_ms)
+    }
+}
+
+/// The width of the box, a number of columns or `"auto"` to fit the terminal
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(untagged, expecting = "width must be a number of columns or \"auto\"")]
+pub(crate) enum Width {
+    Columns(usize),
+    Auto(Auto),
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Auto {
+    Auto,
+}
+
+/// What to show in place of a module that timed out
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum OnTimeout {
+    Placeholder,
+    Omit,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
 pub(crate) struct Greeting {
     pub(crate) enabled: bool,
+    /// Placeholders: `{greeting}` and `{name}`
+    pub(crate) format: String,
+    /// `MM-DD`, greets with a happy birthday on that day
+    pub(crate) birthday: Option<String>,
+    pub(crate) windows: GreetingWindows,
+    pub(crate) phrases: GreetingPhrases,
+    pub(crate) events: Vec<GreetingEvent>,
+}
+
+impl Default for Greeting {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            format: "{greeting}, {name}!".into(),
+            birthday: None,
+            windows: GreetingWindows::default(),
+            phrases: GreetingPhrases::default(),
+            events: Vec::new(),
+        }
+    }
+}
+
+/// The hour each part of the day starts at, the last one lasts past midnight
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct GreetingWindows {
+    pub(crate) morning: u32,
+    pub(crate) afternoon: u32,
+    pub(crate) evening: u32,
+    pub(crate) night: u32,
+}
+
+impl Default for GreetingWindows {
+    fn default() -> Self {
+        Self {
+            morning: 6,
+            afternoon: 12,
+            evening: 18,
+            night: 23,
+        }
+    }
 }
 
-#[derive(Deserialize)]
+/// Phrases to pick one from at random for each part of the day.
+/// Empty lists use the phrase of the locale.
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+pub(crate) struct GreetingPhrases {
+    pub(crate) morning: Vec<String>,
+    pub(crate) afternoon: Vec<String>,
+    pub(crate) evening: Vec<String>,
+    pub(crate) night: Vec<String>,
+}
+
+/// A message that replaces the greeting on a given day. Its `Default` is only the
+/// shape `config check` compares `[[greeting.events]]` tables against.
+#[derive(Serialize, Deserialize, Default)]
+pub(crate) struct GreetingEvent {
+    /// `MM-DD` for every year, or `YYYY-MM-DD` for a single day
+    pub(crate) date: String,
+    pub(crate) message: String,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
 pub(crate) struct Icons {
     pub(crate) enabled: bool,
-    pub(crate) kind: Option<String>,
+    pub(crate) kind: String,
+}
+
+impl Default for Icons {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            kind: "normal".to_string(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+pub(crate) struct Locale {
+    /// A language code like `de`, defaults to the one of `LANG` or `LC_MESSAGES`
+    pub(crate) lang: Option<String>,
+    /// Replaces single strings of the language, by their key
+    pub(crate) strings: BTreeMap<String, String>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct Theme {
+    /// The built-in theme to start from, the other keys override parts of it
+    pub(crate) name: String,
+    pub(crate) border: Option<BorderStyle>,
+    pub(crate) border_color: Option<String>,
+    pub(crate) title_color: Option<String>,
+    pub(crate) label_color: Option<String>,
+    pub(crate) value_color: Option<String>,
+    /// Usage rows past their warning or critical threshold
+    pub(crate) warning_color: Option<String>,
+    pub(crate) critical_color: Option<String>,
+    /// The characters usage bars are drawn with
+    pub(crate) bar_filled: Option<String>,
+    pub(crate) bar_empty: Option<String>,
+    /// Colors of single modules, by module name
+    pub(crate) modules: BTreeMap<String, ModuleColors>,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            name: "default".into(),
+            border: None,
+            border_color: None,
+            title_color: None,
+            label_color: None,
+            value_color: None,
+            warning_color: None,
+            critical_color: None,
+            bar_filled: None,
+            bar_empty: None,
+            modules: BTreeMap::new(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+pub(crate) struct ModuleColors {
+    pub(crate) label: Option<String>,
+    pub(crate) value: Option<String>,
 }
 
-#[derive(Deserialize)]
+/// The characters the box is drawn with
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum BorderStyle {
+    Rounded,
+    Square,
+    Double,
+    Ascii,
+    None,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct Logo {
+    pub(crate) enabled: bool,
+    pub(crate) position: Position,
+    /// A built-in logo to use instead of the distro's one
+    pub(crate) name: Option<String>,
+    /// A text file to use as logo, may contain ANSI colors
+    pub(crate) path: Option<String>,
+    /// Overrides the color of a built-in logo
+    pub(crate) color: Option<String>,
+    /// Columns between the logo and the box
+    pub(crate) gap: usize,
+}
+
+impl Default for Logo {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            position: Position::Left,
+            name: None,
+            path: None,
+            color: None,
+            gap: 2,
+        }
+    }
+}
+
+/// Which side of the box the logo is shown on
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Position {
+    Left,
+    Right,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
 pub(crate) struct Time {
     pub(crate) enabled: bool,
-    pub(crate) kind: Option<String>,
+    pub(crate) kind: String,
+    /// Placeholders: `{date}`, `{time}`, `{weekday}`, `{month}`, `{day}` and `{year}`
+    pub(crate) format: String,
+}
+
+impl Default for Time {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            kind: "12h".to_string(),
+            format: "{date}, {time}".into(),
+        }
+    }
 }
 
-#[derive(Deserialize)]
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
 pub(crate) struct Weather {
     pub(crate) enabled: bool,
+    pub(crate) timeout_ms: Option<u64>,
+    /// Seconds to reuse a fetched value for, 0 disables caching
+    pub(crate) cache_ttl: u64,
+    /// Placeholders: `{main}`, `{temp}` and `{unit}`
+    pub(crate) format: String,
     pub(crate) values: WeatherValues,
 }
 
-#[derive(Deserialize)]
+impl Default for Weather {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            timeout_ms: None,
+            cache_ttl: 30 * 60,
+            format: "{main} {temp}°{unit}".into(),
+            values: WeatherValues::default(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
 pub(crate) struct WeatherValues {
     pub(crate) api_key: Option<String>,
     pub(crate) location: Option<String>,
-    pub(crate) lang: Option<String>,
-    pub(crate) units: Option<String>,
+    pub(crate) lang: String,
+    pub(crate) units: String,
+}
+
+impl Default for WeatherValues {
+    fn default() -> Self {
+        Self {
+            api_key: None,
+            location: None,
+            lang: "en".to_string(),
+            units: "metric".to_string(),
+        }
+    }
 }
 
-#[derive(Deserialize)]
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
 pub(crate) struct System {
     pub(crate) release: Release,
     pub(crate) kernel: Kernel,
     pub(crate) mem_usage: MemUsage,
+    pub(crate) swap: SwapUsage,
     pub(crate) disk_usage: DiskUsage,
     pub(crate) desktop_env: DesktopEnv,
+    pub(crate) uptime: Uptime,
+    pub(crate) load: Load,
+    pub(crate) processes: Processes,
+    pub(crate) cpu: Cpu,
+    pub(crate) cpu_usage: CpuUsage,
 }
 
-#[derive(Deserialize)]
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
 pub(crate) struct Release {
     pub(crate) enabled: bool,
+    /// Placeholders: `{name}`
+    pub(crate) format: String,
 }
 
-#[derive(Deserialize)]
+impl Default for Release {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            format: "{name}".into(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
 pub(crate) struct Kernel {
     pub(crate) enabled: bool,
+    /// Placeholders: `{version}`
+    pub(crate) format: String,
+}
+
+impl Default for Kernel {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            format: "{version}".into(),
+        }
+    }
 }
 
-#[derive(Deserialize)]
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
 pub(crate) struct MemUsage {
     pub(crate) enabled: bool,
+    /// Placeholders: `{used}`, `{free}`, `{total}` and `{percent}` (used)
+    pub(crate) format: Option<String>,
+    /// Picks the default `format`, kept for configs from before there was one
     pub(crate) free_before_used: Option<bool>,
+    pub(crate) used: UsedMemory,
+    /// Draws a usage bar in front of the text
+    pub(crate) bar: bool,
+    /// Percentages used at which the row turns to the theme's warning and critical colors
+    pub(crate) warning: Option<f64>,
+    pub(crate) critical: Option<f64>,
+}
+
+impl Default for MemUsage {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            format: None,
+            free_before_used: None,
+            used: UsedMemory::Available,
+            bar: false,
+            warning: Some(80.0),
+            critical: Some(90.0),
+        }
+    }
+}
+
+/// What counts as used memory
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum UsedMemory {
+    /// Everything but `MemAvailable`, like `free` and `htop` show it
+    Available,
+    /// Everything but `MemFree`, which counts caches and buffers as used
+    Free,
 }
 
-#[derive(Deserialize)]
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct SwapUsage {
+    pub(crate) enabled: bool,
+    /// Placeholders: `{used}`, `{free}`, `{total}` and `{percent}` (used)
+    pub(crate) format: Option<String>,
+    /// Draws a usage bar in front of the text
+    pub(crate) bar: bool,
+    /// Percentages used at which the row turns to the theme's warning and critical colors
+    pub(crate) warning: Option<f64>,
+    pub(crate) critical: Option<f64>,
+}
+
+impl Default for SwapUsage {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            format: None,
+            bar: false,
+            warning: Some(50.0),
+            critical: Some(80.0),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
 pub(crate) struct DiskUsage {
     pub(crate) enabled: bool,
+    /// Placeholders: `{used}`, `{free}`, `{total}`, `{percent}` (used) and `{mount}`
+    pub(crate) format: Option<String>,
+    /// Picks the default `format`, kept for configs from before there was one
     pub(crate) free_before_used: Option<bool>,
+    /// Draws a usage bar in front of the text
+    pub(crate) bar: bool,
+    /// Percentages used at which the row turns to the theme's warning and critical colors
+    pub(crate) warning: Option<f64>,
+    pub(crate) critical: Option<f64>,
+    pub(crate) mounts: Mounts,
+    /// Filesystem types left out with `mounts = "all"`
+    pub(crate) exclude_fstypes: Vec<String>,
+}
+
+impl Default for DiskUsage {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            format: None,
+            free_before_used: None,
+            bar: false,
+            warning: Some(80.0),
+            critical: Some(90.0),
+            mounts: Mounts::Paths(vec!["/".into()]),
+            exclude_fstypes: [
+                "tmpfs", "devtmpfs", "overlay", "squashfs", "ramfs", "efivarfs",
+            ]
+            .iter()
+            .map(ToString::to_string)
+            .collect(),
+        }
+    }
 }
 
-#[derive(Deserialize)]
+/// The mounts to show disk usage for, a list of mount points or `"all"`
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(
+    untagged,
+    expecting = "mounts must be a list of mount points or \"all\""
+)]
+pub(crate) enum Mounts {
+    Paths(Vec<String>),
+    All(All),
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum All {
+    All,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
 pub(crate) struct DesktopEnv {
     pub(crate) enabled: bool,
+    /// Placeholders: `{name}`
+    pub(crate) format: String,
+}
+
+impl Default for DesktopEnv {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            format: "{name}".into(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct Uptime {
+    pub(crate) enabled: bool,
+    /// Placeholders: `{uptime}` (like "3d 4h"), `{days}`, `{hours}`, `{minutes}` and `{boot}`
+    pub(crate) format: String,
+}
+
+impl Default for Uptime {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            format: "{uptime}".into(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct Load {
+    pub(crate) enabled: bool,
+    /// Placeholders: `{one}`, `{five}` and `{fifteen}`, the load averages over minutes
+    pub(crate) format: String,
+}
+
+impl Default for Load {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            format: "{one} {five} {fifteen}".into(),
+        }
+    }
 }
 
-#[derive(Deserialize)]
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+pub(crate) struct Processes {
+    pub(crate) enabled: bool,
+    /// Placeholders: `{count}` (running) and `{total}`, defaults to a sentence like
+    /// "3 running processes"
+    pub(crate) format: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct Cpu {
+    pub(crate) enabled: bool,
+    /// Placeholders: `{model}`, `{cores}` (physical), `{threads}` (logical) and `{frequency}`
+    pub(crate) format: String,
+}
+
+impl Default for Cpu {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            format: "{model} ({threads}) @ {frequency}".into(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct CpuUsage {
+    pub(crate) enabled: bool,
+    /// Placeholders: `{percent}`
+    pub(crate) format: String,
+    /// Milliseconds to measure the usage over, longer is more accurate
+    pub(crate) interval_ms: u64,
+    pub(crate) timeout_ms: Option<u64>,
+}
+
+impl Default for CpuUsage {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            format: "{percent}%".into(),
+            interval_ms: 200,
+            timeout_ms: None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
 pub(crate) struct Packages {
     pub(crate) package_managers: Option<toml::Value>,
     pub(crate) package_count: PackageCount,
     pub(crate) update_count: UpdateCount,
 }
 
-#[derive(Deserialize)]
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
 pub(crate) struct PackageCount {
     pub(crate) enabled: bool,
+    /// Placeholder: `{count}`, defaults to a sentence like "3 updates"
+    pub(crate) format: Option<String>,
+    pub(crate) timeout_ms: Option<u64>,
+    /// Seconds to reuse a counted value for, 0 disables caching
+    pub(crate) cache_ttl: u64,
+}
+
+impl Default for PackageCount {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            format: None,
+            timeout_ms: None,
+            cache_ttl: 60 * 60,
+        }
+    }
 }
 
-#[derive(Deserialize)]
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
 pub(crate) struct UpdateCount {
     pub(crate) enabled: bool,
+    /// Placeholder: `{count}`, defaults to a sentence like "3 updates"
+    pub(crate) format: Option<String>,
+    pub(crate) timeout_ms: Option<u64>,
+    /// Seconds to reuse a counted value for, 0 disables caching
+    pub(crate) cache_ttl: u64,
 }
 
-#[derive(Deserialize)]
+impl Default for UpdateCount {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            format: None,
+            timeout_ms: None,
+            cache_ttl: 6 * 60 * 60,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
 pub(crate) struct Song {
     pub(crate) enabled: bool,
     pub(crate) mode: Option<String>,
+    pub(crate) timeout_ms: Option<u64>,
+    /// Placeholders: `{artist}` and `{title}`
+    pub(crate) format: String,
+}
+
+impl Default for Song {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            mode: None,
+            timeout_ms: None,
+            format: "{artist} - {title}".into(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct Fortune {
+    pub(crate) enabled: bool,
+    /// A `%`-separated fortune file or a directory of them, defaults to the system's fortunes
+    pub(crate) path: Option<String>,
+    pub(crate) pick: Pick,
+    /// Skips quotes that are longer than this many columns, 0 allows any length
+    pub(crate) max_length: usize,
+}
+
+impl Default for Fortune {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            path: None,
+            pick: Pick::Random,
+            max_length: 160,
+        }
+    }
+}
+
+/// How the fortune is chosen
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Pick {
+    /// A different one on every run
+    Random,
+    /// The same one for the whole day
+    Daily,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct Battery {
+    pub(crate) enabled: bool,
+    /// Where the kernel lists power supplies, can point at a copy for testing
+    pub(crate) path: String,
+    /// Placeholders: `{percent}`, `{state}`, `{time}` (until empty or full) and `{ac}`
+    pub(crate) format: Option<String>,
+    /// Draws a charge bar in front of the text
+    pub(crate) bar: bool,
+    /// Percentages of charge at or below which the row turns to the theme's warning and
+    /// critical colors, while discharging
+    pub(crate) warning: Option<f64>,
+    pub(crate) critical: Option<f64>,
+}
+
+impl Default for Battery {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            path: "/sys/class/power_supply".into(),
+            format: None,
+            bar: false,
+            warning: Some(20.0),
+            critical: Some(10.0),
+        }
+    }
+}
+
+/// A `[[custom]]` module, which shows the output of a command. Its `Default` is only
+/// the shape `config check` compares `[[custom]]` tables against.
+#[derive(Serialize, Deserialize, Default)]
+pub(crate) struct CustomModule {
+    pub(crate) name: String,
+    pub(crate) command: String,
+    #[serde(default)]
+    pub(crate) args: Vec<String>,
+    /// Run `command` as a `sh -c` script, with `args` as its arguments
+    #[serde(default)]
+    pub(crate) shell: bool,
+    pub(crate) icon: Option<String>,
+    /// Shows the first capture group instead of the first line of output
+    pub(crate) regex: Option<String>,
+    pub(crate) timeout_ms: Option<u64>,
+    /// Seconds to reuse the output for, 0 disables caching
+    #[serde(default)]
+    pub(crate) cache_ttl: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct Layout {
+    pub(crate) modules: Vec<String>,
+    /// What to do with text that does not fit into the box
+    pub(crate) overflow: Overflow,
+    /// Overrides `overflow` for single modules, by module name
+    pub(crate) module_overflow: BTreeMap<String, Overflow>,
+    pub(crate) style: Style,
+    /// Labels for the labelled style, by module name
+    pub(crate) labels: BTreeMap<String, String>,
+    pub(crate) label_align: LabelAlign,
+    /// Put between a label and its value
+    pub(crate) separator: String,
+}
+
+/// How a row is laid out
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Style {
+    /// Icon and value
+    Plain,
+    /// Icon, label and value
+    Labelled,
+}
+
+/// Which side labels are lined up on
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum LabelAlign {
+    Left,
+    Right,
+}
+
+/// How text that is too long for the box is shown
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Overflow {
+    /// Cut off with an ellipsis
+    Truncate,
+    /// Continued on further rows
+    Wrap,
+    /// Scrolled through, a column further on every run
+    Marquee,
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        Self {
+            modules: MODULE_NAMES.iter().map(ToString::to_string).collect(),
+            overflow: Overflow::Truncate,
+            module_overflow: BTreeMap::new(),
+            style: Style::Plain,
+            labels: BTreeMap::new(),
+            label_align: LabelAlign::Right,
+            separator: ":".into(),
+        }
+    }
+}
+
+impl Layout {
+    pub(crate) fn shows(&self, module: &str) -> bool {
+        self.modules.iter().any(|m| m == module)
+    }
+
+    pub(crate) fn overflow(&self, module: &str) -> Overflow {
+        self.module_overflow
+            .get(module)
+            .copied()
+            .unwrap_or(self.overflow)
+    }
 }
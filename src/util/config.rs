@@ -1,139 +1,621 @@
 use {
-    crate::util::conf_structs::Config,
-    argparse::{ArgumentParser, Store, StoreTrue},
-    std::{env, io::ErrorKind},
+    crate::util::{conf_structs::Config, statics::ARGS},
+    argparse::{ArgumentParser, List, Store, StoreOption, StoreTrue},
+    regex::Regex,
+    serde_path_to_error::{Path as KeyPath, Segment},
+    std::{
+        env,
+        ffi::OsString,
+        fmt, fs,
+        io::{self, stderr, stdout, ErrorKind},
+        path::{Path, PathBuf},
+        process::exit,
+        str::FromStr,
+    },
 };
 
-pub(crate) fn read_config() -> Config {
-    let mut path = format!("{}/.config/draconis/config.toml", env::var("HOME").unwrap());
+/// How the collected information is printed
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Format {
+    Box,
+    Json,
+    Toml,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "box" => Ok(Format::Box),
+            "json" => Ok(Format::Json),
+            "toml" => Ok(Format::Toml),
+            other => Err(format!("unknown format: {}", other)),
+        }
+    }
+}
+
+/// When to color the output
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ColorChoice {
+    Auto,
+    Always,
+    Never,
+}
+
+impl FromStr for ColorChoice {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(ColorChoice::Auto),
+            "always" => Ok(ColorChoice::Always),
+            "never" => Ok(ColorChoice::Never),
+            other => Err(format!("unknown color choice: {}", other)),
+        }
+    }
+}
+
+/// A subcommand to run instead of printing the greeter
+pub(crate) enum Command {
+    /// Write the default config, overwriting an existing one only if `force` is set
+    Init { force: bool },
+    /// Validate the config and list unknown keys
+    Check,
+    /// Print the effective config, including defaults
+    Dump,
+}
+
+pub(crate) struct Args {
+    pub(crate) config: Option<String>,
+    pub(crate) format: Format,
+    pub(crate) color: ColorChoice,
+    pub(crate) command: Option<Command>,
+    /// Refresh the cached value of this module and exit, used by background refreshes
+    pub(crate) refresh: Option<String>,
+}
+
+/// Parses what follows `draconis config`. `-c` is accepted here too, as in
+/// `draconis config check -c file.toml`, and overrides one given before `config`.
+fn parse_config_command(args: Vec<String>, config: &mut Option<String>) -> Command {
+    let mut action = String::new();
+    let mut force = false;
+    {
+        let mut ap = ArgumentParser::new();
+        ap.set_description("Manage the Draconis config file");
+        ap.refer(&mut action).required().add_argument(
+            "action",
+            Store,
+            "init (write the default config), check (validate the config) or dump (print the effective config)",
+        );
+        ap.refer(&mut force).add_option(
+            &["--force"],
+            StoreTrue,
+            "Overwrite an existing config file with init",
+        );
+        ap.refer(config).add_option(
+            &["-c", "--config"],
+            StoreOption,
+            "Specify a path to a config file",
+        );
+        if let Err(code) = ap.parse(args, &mut stdout(), &mut stderr()) {
+            exit(code);
+        }
+    }
+
+    match action.as_str() {
+        "init" => Command::Init { force },
+        "check" => Command::Check,
+        "dump" => Command::Dump,
+        other => {
+            eprintln!("Unknown config action: {}", other);
+            exit(2);
+        }
+    }
+}
+
+pub(crate) fn parse_args() -> Args {
+    let mut args = Args {
+        config: None,
+        format: Format::Box,
+        color: ColorChoice::Auto,
+        command: None,
+        refresh: None,
+    };
     let mut ver = false;
+    let mut command = String::new();
+    let mut command_args: Vec<String> = Vec::new();
     {
         let mut ap = ArgumentParser::new();
         ap.set_description("A simple greeter for your terminal, made in Rust");
-        ap.refer(&mut path).add_option(
+        ap.refer(&mut args.config).add_option(
             &["-c", "--config"],
-            Store,
+            StoreOption,
             "Specify a path to a config file",
         );
+        ap.refer(&mut args.format).add_option(
+            &["-f", "--format"],
+            Store,
+            "Output format: box, json or toml",
+        );
+        ap.refer(&mut args.color).add_option(
+            &["--color"],
+            Store,
+            "When to use colors: auto, always or never",
+        );
         ap.refer(&mut ver)
             .add_option(&["-v", "--version"], StoreTrue, "View program version");
+        ap.refer(&mut args.refresh).add_option(
+            &["--refresh"],
+            StoreOption,
+            "Refresh the cached value of a module (updates, packages or weather) and exit",
+        );
+        ap.refer(&mut command)
+            .add_argument("command", Store, "Optional subcommand: config");
+        ap.refer(&mut command_args)
+            .add_argument("arguments", List, "Arguments for the subcommand");
+        ap.stop_on_first_argument(true);
         ap.parse_args_or_exit();
     }
 
     if ver {
         println!("Draconis v{}", env!("CARGO_PKG_VERSION"));
-        std::process::exit(0);
+        exit(0);
     }
 
-    let content = match std::fs::read_to_string(path) {
-        Err(e) if e.kind() == ErrorKind::NotFound => {
-            return toml::from_str(
-                r#"
-                [main]
+    match command.as_str() {
+        "" => (),
+        "config" => {
+            command_args.insert(0, "draconis config".to_string());
+            args.command = Some(parse_config_command(command_args, &mut args.config));
+        }
+        other => {
+            eprintln!("Unknown command: {}", other);
+            exit(2);
+        }
+    }
 
-                [util]
+    args
+}
 
-                [greeting]
-                enabled = true
+/// Something went wrong while loading the config file
+pub(crate) enum ConfigError {
+    Read {
+        path: String,
+        source: io::Error,
+    },
+    Parse {
+        path: String,
+        content: String,
+        /// The key whose value is wrong, `None` for syntax errors
+        key: Option<KeyPath>,
+        source: toml::de::Error,
+    },
+}
 
-                [icons]
-                enabled = false
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Read { path, source } => {
+                writeln!(f, "error: could not read config file {}", path)?;
+                write!(f, "  = {}", source)
+            }
+            ConfigError::Parse {
+                path,
+                content,
+                key,
+                source,
+            } => {
+                // toml appends the key and position to its message, they are shown separately here
+                let full = source.to_string();
+                let message = full
+                    .split(" for key `")
+                    .next()
+                    .and_then(|m| m.split(" at line ").next())
+                    .unwrap_or(&full);
+                // An empty path is the whole file, as for syntax errors
+                let key = key.as_ref().filter(|key| key.iter().next().is_some());
+                let position = key
+                    .and_then(|key| locate_key(content, key))
+                    .or_else(|| source.line_col());
+                let key = key.map(|key| key.to_string());
+
+                writeln!(f, "error: could not parse config file {}", path)?;
+                if let Some((line, col)) = position {
+                    match key {
+                        Some(key) => writeln!(
+                            f,
+                            "  --> line {}, column {} (key `{}`)",
+                            line + 1,
+                            col + 1,
+                            key
+                        )?,
+                        None => writeln!(f, "  --> line {}, column {}", line + 1, col + 1)?,
+                    }
+                    if let Some(text) = content.lines().nth(line) {
+                        let number = (line + 1).to_string();
+                        let gutter = " ".repeat(number.len());
+                        writeln!(f, "{} |", gutter)?;
+                        writeln!(f, "{} | {}", number, text)?;
+                        writeln!(f, "{} | {}^", gutter, " ".repeat(col))?;
+                    }
+                } else if let Some(key) = key {
+                    writeln!(f, "  --> key `{}`", key)?;
+                }
+                writeln!(f, "  = {}", message)?;
+                write!(f, "  = help: {}", suggestion(message))
+            }
+        }
+    }
+}
 
-                [time]
-                enabled = false
+/// Where `key` is set in `content`, as the line and column of its `key =`, or of the
+/// header of the table it names. For wrong values toml only knows how far it had read,
+/// which for enums is often the next table header.
+fn locate_key(content: &str, key: &KeyPath) -> Option<(usize, usize)> {
+    // Tables in an array of tables are told apart by their index, like `custom.#1`
+    let wanted: Vec<String> = key
+        .iter()
+        .map(|segment| match segment {
+            Segment::Seq { index } => format!("#{}", index),
+            Segment::Map { key } => key.clone(),
+            Segment::Enum { variant } => variant.clone(),
+            Segment::Unknown => "?".to_string(),
+        })
+        .collect();
+
+    let header = Regex::new(r#"^\s*(\[\[?)\s*([\w.\-" ]+?)\s*\]\]?\s*(#.*)?$"#).unwrap();
+    let assignment = Regex::new(r#"^\s*([\w.\-" ]+?)\s*="#).unwrap();
+    let split = |names: &str| -> Vec<String> {
+        names
+            .split('.')
+            .map(|name| name.trim().trim_matches('"').to_string())
+            .collect()
+    };
 
-                [weather]
-                enabled = false
+    let mut table: Vec<String> = Vec::new();
+    let mut arrays: Vec<(Vec<String>, usize)> = Vec::new();
+    // Every key and table in the file, with where it is set
+    let mut found: Vec<(Vec<String>, (usize, usize))> = Vec::new();
+    for (number, line) in content.lines().enumerate() {
+        let column = line.len() - line.trim_start().len();
+        if let Some(captures) = header.captures(line) {
+            table = split(&captures[2]);
+            if &captures[1] == "[[" {
+                let index = match arrays.iter_mut().find(|(name, _)| *name == table) {
+                    Some((_, count)) => {
+                        *count += 1;
+                        *count - 1
+                    }
+                    None => {
+                        arrays.push((table.clone(), 1));
+                        0
+                    }
+                };
+                table.push(format!("#{}", index));
+            }
+            found.push((table.clone(), (number, column)));
+        } else if let Some(captures) = assignment.captures(line) {
+            let mut path = table.clone();
+            path.extend(split(&captures[1]));
+            found.push((path, (number, column)));
+        }
+    }
 
-                [weather.values]
+    // The key itself may be missing, like a required field, so its table is looked for next
+    (1..=wanted.len()).rev().find_map(|length| {
+        found
+            .iter()
+            .find(|(path, _)| path[..] == wanted[..length])
+            .map(|(_, position)| *position)
+    })
+}
 
-                [system]
+/// A hint on how to fix the most common kinds of mistakes
+fn suggestion(message: &str) -> &'static str {
+    if message.starts_with("missing field") {
+        "add the missing field to this table"
+    } else if message.starts_with("invalid type") {
+        "check the type of the value, text needs quotes while numbers and booleans must not have them"
+    } else if message.starts_with("unknown variant") {
+        "use one of the expected values listed above"
+    } else if message.starts_with("redefinition of table")
+        || message.starts_with("duplicate key")
+        || message.starts_with("duplicate field")
+    {
+        "every table and key may only appear once, merge the duplicates"
+    } else if message.starts_with("expected")
+        || message.starts_with("unexpected")
+        || message.starts_with("unterminated")
+        || message.starts_with("newline")
+        || message.starts_with("invalid")
+    {
+        "check this line for a missing quote, bracket or `=`"
+    } else {
+        "compare your config with example_config.toml"
+    }
+}
 
-                [system.release]
-                enabled = false
+/// The config file of the current user. `-c` and `$DRACONIS_CONFIG` are used as
+/// given, otherwise the first existing file in the XDG config home or
+/// `~/.config` is picked, falling back to the first of those that could exist.
+pub(crate) fn user_config_path() -> Option<PathBuf> {
+    if let Some(path) = &ARGS.config {
+        return Some(PathBuf::from(path));
+    }
+    if let Some(path) = env::var_os("DRACONIS_CONFIG").filter(|p| !p.is_empty()) {
+        return Some(PathBuf::from(path));
+    }
 
-                [system.kernel]
-                enabled = false
+    user_config_in(env::var_os("XDG_CONFIG_HOME"), env::var_os("HOME"))
+}
 
-                [system.mem_usage]
-                enabled = false
+/// The config file in `config_home` or else in `.config` of `home`
+fn user_config_in(config_home: Option<OsString>, home: Option<OsString>) -> Option<PathBuf> {
+    let candidates: Vec<PathBuf> = [
+        config_home.filter(|p| !p.is_empty()).map(PathBuf::from),
+        home.filter(|p| !p.is_empty())
+            .map(|home| PathBuf::from(home).join(".config")),
+    ]
+    .into_iter()
+    .flatten()
+    .map(|dir| dir.join("draconis/config.toml"))
+    .collect();
+
+    candidates
+        .iter()
+        .find(|path| path.exists())
+        .or_else(|| candidates.first())
+        .cloned()
+}
 
-                [system.disk_usage]
-                enabled = false
+/// The system-wide config file from `$XDG_CONFIG_DIRS`, `/etc/xdg` by default
+pub(crate) fn system_config_path() -> Option<PathBuf> {
+    let dirs = env::var("XDG_CONFIG_DIRS")
+        .ok()
+        .filter(|dirs| !dirs.is_empty())
+        .unwrap_or_else(|| "/etc/xdg".to_string());
 
-                [system.desktop_env]
-                enabled = false
+    system_config_in(&dirs)
+}
 
-                [packages]
+/// The config file in the first of the `:` separated `dirs` that has one
+fn system_config_in(dirs: &str) -> Option<PathBuf> {
+    dirs.split(':')
+        .filter(|dir| !dir.is_empty())
+        .map(|dir| Path::new(dir).join("draconis/config.toml"))
+        .find(|path| path.exists())
+}
 
-                [packages.package_count]
-                enabled = false
+/// Every config file to load, the ones listed later override earlier ones
+pub(crate) fn config_paths() -> Vec<PathBuf> {
+    [system_config_path(), user_config_path()]
+        .into_iter()
+        .flatten()
+        .collect()
+}
 
-                [packages.update_count]
-                enabled = false
+/// Expands a leading `~/` to the home directory
+pub(crate) fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), env::var_os("HOME")) {
+        (Some(rest), Some(home)) => PathBuf::from(home).join(rest),
+        _ => PathBuf::from(path),
+    }
+}
 
-                [song]
-                enabled = false
-                "#,
-            )
-            .unwrap()
+/// Reads a single config file, `None` if it does not exist or is empty
+pub(crate) fn load_file(path: &Path) -> Result<Option<toml::Value>, ConfigError> {
+    let content = match fs::read_to_string(path) {
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(source) => {
+            return Err(ConfigError::Read {
+                path: path.display().to_string(),
+                source,
+            })
         }
-        Err(e) => panic!("{}", e),
-        Ok(content) => match content.as_ref() {
-            "" => {
-                return toml::from_str(
-                    r#"
-                [main]
+        Ok(content) if content.trim().is_empty() => return Ok(None),
+        Ok(content) => content,
+    };
 
-                [util]
+    // Deserializing each file on its own keeps line numbers in errors pointing at that file
+    let checked =
+        serde_path_to_error::deserialize::<_, Config>(&mut toml::Deserializer::new(&content))
+            .map_err(|e| (Some(e.path().clone()), e.into_inner()))
+            .and_then(|_| toml::from_str(&content).map_err(|e| (None, e)));
+
+    match checked {
+        Ok(value) => Ok(Some(value)),
+        Err((key, source)) => Err(ConfigError::Parse {
+            path: path.display().to_string(),
+            content,
+            key,
+            source,
+        }),
+    }
+}
 
-                [greeting]
-                enabled = true
+/// Merges `over` into `base`, tables are merged key by key and everything else is replaced
+fn merge(base: &mut toml::Value, over: toml::Value) {
+    match (base, over) {
+        (toml::Value::Table(base), toml::Value::Table(over)) => {
+            for (key, value) in over {
+                match base.get_mut(&key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, over) => *base = over,
+    }
+}
 
-                [icons]
-                enabled = false
+/// Loads and layers all config files in `paths`
+pub(crate) fn load_config(paths: &[PathBuf]) -> Result<Config, ConfigError> {
+    let mut merged = toml::Value::Table(toml::value::Table::new());
+    for path in paths {
+        if let Some(value) = load_file(path)? {
+            merge(&mut merged, value);
+        }
+    }
 
-                [time]
-                enabled = false
+    serde_path_to_error::deserialize(merged).map_err(|e| ConfigError::Parse {
+        path: paths
+            .iter()
+            .map(|path| path.display().to_string())
+            .collect::<Vec<_>>()
+            .join(" + "),
+        content: String::new(),
+        key: Some(e.path().clone()),
+        source: e.into_inner(),
+    })
+}
 
-                [weather]
-                enabled = false
+pub(crate) fn read_config() -> Config {
+    load_config(&config_paths()).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        eprintln!("Falling back to the default config.");
+        Config::default()
+    })
+}
 
-                [weather.values]
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The position `locate_key` finds for the error of deserializing `content`
+    fn position(content: &str) -> Option<(usize, usize)> {
+        let error =
+            serde_path_to_error::deserialize::<_, Config>(&mut toml::Deserializer::new(content))
+                .err()
+                .unwrap();
+        locate_key(content, error.path())
+    }
 
-                [system]
+    #[test]
+    fn points_at_key_not_next_table() {
+        let content = "[util]\nmin_width = 50\n  width = \"wide\"\n\n[song]\nenabled = true\n";
+        assert_eq!(position(content), Some((2, 2)));
+    }
 
-                [system.release]
-                enabled = false
+    #[test]
+    fn points_into_array_of_tables() {
+        let content =
+            "[[custom]]\nname = \"a\"\ncommand = \"x\"\n\n[[custom]]\nname = \"b\"\ncommand = 5\n";
+        assert_eq!(position(content), Some((6, 0)));
+    }
 
-                [system.kernel]
-                enabled = false
+    #[test]
+    fn falls_back_to_table_of_missing_key() {
+        let content = "[main]\nname = \"x\"\n\n[[custom]]\nname = \"a\"\n";
+        assert_eq!(position(content), Some((3, 0)));
+    }
 
-                [system.mem_usage]
-                enabled = false
+    #[test]
+    fn duplicates_get_the_merge_hint() {
+        let merge = "every table and key may only appear once, merge the duplicates";
+        for content in [
+            "[song]\nenabled = true\nenabled = false\n",
+            "[song]\nenabled = true\n\n[song]\nmode = \"music\"\n",
+        ] {
+            let error = toml::from_str::<Config>(content).err().unwrap();
+            assert_eq!(suggestion(&error.to_string()), merge, "{}", error);
+        }
+    }
 
-                [system.disk_usage]
-                enabled = false
+    /// A fresh temporary directory, removed on drop
+    struct TempDir(PathBuf);
 
-                [system.desktop_env]
-                enabled = false
+    impl TempDir {
+        fn new(name: &str) -> TempDir {
+            let dir = env::temp_dir().join(format!("draconis-{}-{}", name, std::process::id()));
+            let _ = fs::remove_dir_all(&dir);
+            fs::create_dir_all(&dir).unwrap();
+            TempDir(dir)
+        }
 
-                [packages]
+        /// Writes `draconis/config.toml` under `dir`, returning its path
+        fn config(&self, dir: &str, content: &str) -> PathBuf {
+            let path = self.0.join(dir).join("draconis/config.toml");
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(&path, content).unwrap();
+            path
+        }
+    }
 
-                [packages.package_count]
-                enabled = false
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
 
-                [packages.update_count]
-                enabled = false
+    #[test]
+    fn xdg_config_home_comes_before_dot_config() {
+        let temp = TempDir::new("user-config");
+        let xdg = Some(temp.0.join("xdg").into_os_string());
+        let home = Some(temp.0.join("home").into_os_string());
+
+        // Nothing exists yet, so the file to create is the XDG one
+        let xdg_path = temp.0.join("xdg/draconis/config.toml");
+        assert_eq!(
+            user_config_in(xdg.clone(), home.clone()),
+            Some(xdg_path.clone())
+        );
 
-                [song]
-                enabled = false
-                "#,
-                )
-                .unwrap()
-            }
-            _ => content,
-        },
-    };
-    toml::from_str(&content).unwrap()
+        let dot_config = temp.config("home/.config", "");
+        assert_eq!(user_config_in(xdg.clone(), home.clone()), Some(dot_config));
+
+        temp.config("xdg", "");
+        assert_eq!(user_config_in(xdg, home.clone()), Some(xdg_path));
+        assert_eq!(
+            user_config_in(Some(OsString::new()), None),
+            None,
+            "empty variables count as unset"
+        );
+    }
+
+    #[test]
+    fn first_system_dir_with_a_config_wins() {
+        let temp = TempDir::new("system-config");
+        let second = temp.config("second", "");
+        let third = temp.config("third", "");
+        let dirs = [
+            temp.0.join("first"),
+            temp.0.join("second"),
+            temp.0.join("third"),
+        ]
+        .map(|dir| dir.display().to_string())
+        .join(":");
+
+        assert_eq!(system_config_in(&dirs), Some(second));
+        assert_eq!(
+            system_config_in(&format!(":{}", temp.0.join("third").display())),
+            Some(third)
+        );
+        assert_eq!(
+            system_config_in(&temp.0.join("first").display().to_string()),
+            None
+        );
+    }
+
+    #[test]
+    fn later_files_override_earlier_ones() {
+        let temp = TempDir::new("layered-config");
+        let system = temp.config(
+            "system",
+            "[main]\nname = \"system\"\nhostname = \"box\"\n\n[layout]\nmodules = [\"uptime\", \"song\"]\n",
+        );
+        let user = temp.config(
+            "user",
+            "[main]\nname = \"user\"\n\n[layout]\nmodules = [\"song\"]\n",
+        );
+        let missing = temp.0.join("missing/draconis/config.toml");
+
+        let config = load_config(&[system, missing, user]).unwrap_or_else(|e| panic!("{}", e));
+        assert_eq!(config.main.name.as_deref(), Some("user"));
+        // Tables are merged key by key, arrays are replaced as a whole
+        assert_eq!(config.main.hostname.as_deref(), Some("box"));
+        assert_eq!(config.layout.modules, ["song"]);
+    }
 }
@@ -1,6 +1,15 @@
 use {
-    crate::util::statics::CONF, unicode_segmentation::UnicodeSegmentation,
-    unicode_width::UnicodeWidthStr,
+    crate::util::{
+        conf_structs::{Overflow, Position, Width},
+        statics::{CONF, LOGO, THEME, WIDTH},
+        theme::paint,
+    },
+    std::{
+        env,
+        time::{SystemTime, UNIX_EPOCH},
+    },
+    unicode_segmentation::UnicodeSegmentation,
+    unicode_width::UnicodeWidthChar,
 };
 
 pub(crate) fn upper_first(s: String) -> String {
@@ -11,42 +20,288 @@ pub(crate) fn upper_first(s: String) -> String {
     }
 }
 
-pub(crate) fn calc_whitespace(text: String) -> String {
-    let size = (CONF.util.width - 5) as usize - text.graphemes(true).count();
-    let fs = format!("{}{}", " ".repeat(size), "│");
-    format!("{}{}", text, fs)
+/// Removes ANSI escape sequences, so that colored text can be measured
+pub(crate) fn strip_ansi(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            out.push(c);
+            continue;
+        }
+        if chars.next() == Some('[') {
+            // Parameters and intermediates run until the final byte, '@' to '~'
+            for c in chars.by_ref() {
+                if ('@'..='~').contains(&c) {
+                    break;
+                }
+            }
+        }
+    }
+
+    out
 }
 
-pub(crate) fn calc_whitespace_song(text: String) -> String {
-    let size = (CONF.util.width - 3) as usize - UnicodeWidthStr::width_cjk(text.as_str());
-    let fs = format!("{}{}", " ".repeat(size), "│");
-    format!("{}{}", text, fs)
+/// Columns `text` takes up in a terminal. Escape sequences take none, and a
+/// character with emoji presentation (VS16) takes two.
+pub(crate) fn display_width(text: &str) -> usize {
+    strip_ansi(text)
+        .graphemes(true)
+        .map(|g| {
+            if g.contains('\u{fe0f}') {
+                2
+            } else {
+                g.chars().map(|c| c.width().unwrap_or(0)).max().unwrap_or(0)
+            }
+        })
+        .sum()
 }
 
-pub(crate) fn calc_with_hostname(text: String) -> String {
-    let size = if CONF.icons.enabled {
-        match CONF.icons.kind.as_deref() {
-            Some("emoji") => (CONF.util.width + 5) as usize - text.graphemes(true).count(),
-            Some(&_) | None => (CONF.util.width + 4) as usize - text.graphemes(true).count(),
+/// Columns of the terminal Draconis prints to, from the first of stdout, stderr
+/// and stdin that is a terminal, or `$COLUMNS` if none is
+pub(crate) fn terminal_width() -> Option<usize> {
+    for fd in [libc::STDOUT_FILENO, libc::STDERR_FILENO, libc::STDIN_FILENO] {
+        let mut size = libc::winsize {
+            ws_row: 0,
+            ws_col: 0,
+            ws_xpixel: 0,
+            ws_ypixel: 0,
+        };
+        // SAFETY: TIOCGWINSZ only writes a `winsize` to the pointer it is given
+        let result = unsafe { libc::ioctl(fd, libc::TIOCGWINSZ, &mut size) };
+        if result == 0 && size.ws_col > 0 {
+            return Some(size.ws_col as usize);
         }
-    } else {
-        (CONF.util.width + 4) as usize - text.graphemes(true).count()
+    }
+
+    env::var("COLUMNS").ok()?.trim().parse().ok()
+}
+
+/// The width of the box in columns, borders included
+pub(crate) fn box_width() -> usize {
+    match CONF.util.width {
+        Width::Columns(columns) => columns,
+        Width::Auto(_) => terminal_width()
+            .map(|columns| columns.saturating_sub(logo_width()))
+            .unwrap_or(CONF.util.min_width)
+            .min(CONF.util.max_width)
+            .max(CONF.util.min_width),
+    }
+}
+
+/// Columns taken by the logo and the gap next to it, 0 without a logo
+fn logo_width() -> usize {
+    match &*LOGO {
+        Some(logo) => logo.iter().map(|l| display_width(l)).max().unwrap_or(0) + CONF.logo.gap,
+        None => 0,
+    }
+}
+
+/// Puts the logo beside the lines of the box, if there is one. The logo is
+/// padded to a block of even width, and the shorter of the two filled up
+/// with blank rows so both line up.
+pub(crate) fn beside_logo(lines: Vec<String>) -> Vec<String> {
+    let logo = match &*LOGO {
+        Some(logo) => logo,
+        None => return lines,
     };
+    let art_width = logo_width() - CONF.logo.gap;
+    let gap = " ".repeat(CONF.logo.gap);
+
+    (0..lines.len().max(logo.len()))
+        .map(|i| {
+            let art = logo.get(i).map_or("", String::as_str);
+            let art = format!("{}{}", art, " ".repeat(art_width - display_width(art)));
+            let line = lines.get(i).cloned().unwrap_or_else(|| " ".repeat(*WIDTH));
+            let row = match CONF.logo.position {
+                Position::Left => format!("{}{}{}", art, gap, line),
+                Position::Right => format!("{}{}{}", line, gap, art),
+            };
+            row.trim_end().to_string()
+        })
+        .collect()
+}
+
+/// Cuts `text` down to `columns`, ending it with an ellipsis if anything was cut
+pub(crate) fn truncate(text: &str, columns: usize) -> String {
+    if display_width(text) <= columns {
+        return text.to_string();
+    }
+    // Not even the ellipsis fits
+    if columns == 0 {
+        return String::new();
+    }
+
+    let mut out = String::new();
+    let mut used = 0;
+    for g in text.graphemes(true) {
+        used += display_width(g);
+        if used + 1 > columns {
+            break;
+        }
+        out.push_str(g);
+    }
+    out.push('…');
+
+    out
+}
+
+/// Splits `text` into lines of at most `columns`, breaking between words where possible
+pub(crate) fn wrap(text: &str, columns: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+
+    for word in text.split(' ') {
+        let space = usize::from(!line.is_empty());
+        if display_width(&line) + space + display_width(word) <= columns {
+            if space == 1 {
+                line.push(' ');
+            }
+            line.push_str(word);
+            continue;
+        }
+
+        if !line.is_empty() {
+            lines.push(std::mem::take(&mut line));
+        }
+        // Words longer than a whole line are broken wherever they hit the edge
+        for g in word.graphemes(true) {
+            if !line.is_empty() && display_width(&line) + display_width(g) > columns {
+                lines.push(std::mem::take(&mut line));
+            }
+            line.push_str(g);
+        }
+    }
+    lines.push(line);
 
-    let fs = format!("{}{}", "─".repeat(size), "╮");
-    format!("{}{}", text, fs)
+    lines
 }
 
-pub(crate) fn calc_bottom(text: String) -> String {
-    let size = if CONF.icons.enabled {
-        match CONF.icons.kind.as_deref() {
-            Some("emoji") => (CONF.util.width - 4) as usize - text.graphemes(true).count(),
-            Some(&_) | None => (CONF.util.width - 5) as usize - text.graphemes(true).count(),
+/// A `columns` wide window into `text`, which moves along by a column each second
+/// and starts over from the beginning after a short gap
+pub(crate) fn marquee(text: &str, columns: usize) -> String {
+    if display_width(text) <= columns {
+        return text.to_string();
+    }
+
+    let looped: Vec<&str> = text.graphemes(true).chain(["   "]).collect();
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let start = (seconds % looped.len() as u64) as usize;
+
+    let mut out = String::new();
+    let mut used = 0;
+    for g in looped.iter().cycle().skip(start) {
+        used += display_width(g);
+        if used > columns {
+            break;
         }
-    } else {
-        (CONF.util.width - 5) as usize - text.graphemes(true).count()
+        out.push_str(g);
+    }
+
+    out
+}
+
+/// Makes `text` fit into `columns` as `overflow` says, as one or more lines
+pub(crate) fn fit(text: &str, columns: usize, overflow: Overflow) -> Vec<String> {
+    match overflow {
+        Overflow::Truncate => vec![truncate(text, columns)],
+        Overflow::Wrap => wrap(text, columns),
+        Overflow::Marquee => vec![marquee(text, columns)],
+    }
+}
+
+/// Paints a piece of the border in the theme's border color
+fn border(piece: &str) -> String {
+    paint(THEME.border_color.as_ref(), piece)
+}
+
+/// Puts `text` between the side borders, padded to the width of the box
+pub(crate) fn calc_whitespace(text: String) -> String {
+    let size = WIDTH.saturating_sub(4 + display_width(&text));
+    let vertical = border(THEME.border.vertical);
+    format!("{} {}{} {}", vertical, text, " ".repeat(size), vertical)
+}
+
+/// The top edge of the box, with `title` in it if there is one
+pub(crate) fn calc_top(title: Option<&str>) -> String {
+    let b = &THEME.border;
+    let title = title.map(|title| truncate(title, WIDTH.saturating_sub(8)));
+    let (left, used) = match title {
+        Some(title) => (
+            format!(
+                "{} {} ",
+                border(&format!("{}{}", b.top_left, b.horizontal.repeat(3))),
+                paint(THEME.title_color.as_ref(), &title)
+            ),
+            6 + display_width(&title),
+        ),
+        None => (border(b.top_left), 1),
     };
 
-    let fs = format!("{}{}", "─".repeat(size), "╯");
-    format!("{}{}", text, fs)
+    let size = WIDTH.saturating_sub(used + 1);
+    format!(
+        "{}{}",
+        left,
+        border(&format!("{}{}", b.horizontal.repeat(size), b.top_right))
+    )
+}
+
+/// The bottom edge of the box
+pub(crate) fn calc_bottom() -> String {
+    let b = &THEME.border;
+    border(&format!(
+        "{}{}{}",
+        b.bottom_left,
+        b.horizontal.repeat(WIDTH.saturating_sub(2)),
+        b.bottom_right
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wide_characters_count_twice() {
+        assert_eq!(display_width("日本語"), 6);
+        assert_eq!(display_width("👍🏽"), 2);
+        assert_eq!(display_width("⚙️"), 2);
+        assert_eq!(display_width("\x1b[31mred\x1b[0m"), 3);
+    }
+
+    #[test]
+    fn truncate_keeps_text_that_fits() {
+        assert_eq!(truncate("日本語", 6), "日本語");
+        assert_eq!(truncate("ab👍", 4), "ab👍");
+    }
+
+    #[test]
+    fn truncate_does_not_split_wide_characters() {
+        // 語 would end on the last column, leaving none for the ellipsis
+        assert_eq!(truncate("日本語テキスト", 6), "日本…");
+        assert_eq!(truncate("日本語テキスト", 7), "日本語…");
+        // A skin tone modifier stays with its emoji
+        assert_eq!(truncate("ab👍🏽cd", 4), "ab…");
+        assert_eq!(truncate("ab👍🏽cd", 5), "ab👍🏽…");
+        assert!(display_width(&truncate("ab👍🏽cd", 4)) <= 4);
+        assert_eq!(truncate("日本語テキスト", 0), "");
+    }
+
+    #[test]
+    fn wrap_breaks_between_words() {
+        assert_eq!(wrap("one two three", 7), ["one two", "three"]);
+        assert_eq!(wrap("日本 語テ", 5), ["日本", "語テ"]);
+    }
+
+    #[test]
+    fn wrap_breaks_long_words_at_the_edge() {
+        assert_eq!(wrap("日本語テキ", 5), ["日本", "語テ", "キ"]);
+        assert_eq!(wrap("🎵🎵🎵", 4), ["🎵🎵", "🎵"]);
+        for line in wrap("a日本語テキスト👍🏽👍🏽 b", 5) {
+            assert!(display_width(&line) <= 5, "{}", line);
+        }
+    }
 }
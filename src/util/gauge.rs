@@ -0,0 +1,119 @@
+use crate::util::{formatting::display_width, statics::THEME, theme::Color};
+
+/// A percentage a module can show as a bar and color by thresholds, like
+/// the share of a disk that is used
+pub(crate) struct Gauge {
+    pub(crate) percent: f64,
+    /// Whether to draw a bar in front of the text
+    pub(crate) bar: bool,
+    /// When `critical` is below `warning`, low values are the bad ones,
+    /// as for a battery
+    pub(crate) warning: Option<f64>,
+    pub(crate) critical: Option<f64>,
+}
+
+/// How close a gauge is to its limit
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum Level {
+    Normal,
+    Warning,
+    Critical,
+}
+
+/// Bars narrower than this are put on a row of their own
+const MIN_BAR: usize = 7;
+
+impl Gauge {
+    pub(crate) fn level(&self) -> Level {
+        let falling = matches!((self.warning, self.critical), (Some(w), Some(c)) if c < w);
+        let reached = |threshold: Option<f64>| {
+            threshold.is_some_and(|t| {
+                if falling {
+                    self.percent <= t
+                } else {
+                    self.percent >= t
+                }
+            })
+        };
+
+        if reached(self.critical) {
+            Level::Critical
+        } else if reached(self.warning) {
+            Level::Warning
+        } else {
+            Level::Normal
+        }
+    }
+
+    /// The color of the row at the current level, if it is not the usual one
+    pub(crate) fn color(&self) -> Option<&'static Color> {
+        match self.level() {
+            Level::Normal => None,
+            Level::Warning => THEME.warning_color.as_ref(),
+            Level::Critical => THEME.critical_color.as_ref(),
+        }
+    }
+
+    /// A bar like `[████░░░░]`, `columns` wide including the brackets
+    pub(crate) fn draw(&self, columns: usize) -> String {
+        let cells = columns.saturating_sub(2);
+        let filled = (self.percent.clamp(0.0, 100.0) / 100.0 * cells as f64).round() as usize;
+
+        format!(
+            "[{}{}]",
+            THEME.bar_filled.repeat(filled),
+            THEME.bar_empty.repeat(cells - filled)
+        )
+    }
+
+    /// Puts a bar in front of `lines`, filling the columns the first line
+    /// leaves free, or on a row of its own if there is too little room
+    pub(crate) fn prepend_bar(&self, mut lines: Vec<String>, columns: usize) -> Vec<String> {
+        let first = lines.first().map_or(0, |l| display_width(l));
+        let free = columns.saturating_sub(first + 1);
+
+        if free >= MIN_BAR {
+            lines[0] = format!("{} {}", self.draw(free), lines[0]);
+        } else {
+            lines.insert(0, self.draw(columns));
+        }
+
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn level(percent: f64, warning: Option<f64>, critical: Option<f64>) -> Level {
+        Gauge {
+            percent,
+            bar: false,
+            warning,
+            critical,
+        }
+        .level()
+    }
+
+    #[test]
+    fn high_values_are_bad_by_default() {
+        assert_eq!(level(50.0, Some(80.0), Some(90.0)), Level::Normal);
+        assert_eq!(level(80.0, Some(80.0), Some(90.0)), Level::Warning);
+        assert_eq!(level(95.0, Some(80.0), Some(90.0)), Level::Critical);
+        assert_eq!(level(95.0, None, None), Level::Normal);
+    }
+
+    #[test]
+    fn reversed_thresholds_make_low_values_bad() {
+        assert_eq!(level(50.0, Some(20.0), Some(10.0)), Level::Normal);
+        assert_eq!(level(20.0, Some(20.0), Some(10.0)), Level::Warning);
+        assert_eq!(level(5.0, Some(20.0), Some(10.0)), Level::Critical);
+    }
+
+    #[test]
+    fn a_single_threshold_counts_upwards() {
+        assert_eq!(level(5.0, Some(20.0), None), Level::Normal);
+        assert_eq!(level(95.0, None, Some(90.0)), Level::Critical);
+    }
+}
@@ -0,0 +1,637 @@
+use {
+    crate::util::{conf_structs::Locale, template::fill},
+    std::{collections::BTreeMap, env},
+};
+
+/// The bundled strings of a language, along with its plural rules
+struct Table {
+    lang: &'static str,
+    /// The plural category of a count: `one`, `few`, `many` or `other`
+    plural: fn(u64) -> &'static str,
+    /// The category of an ordinal number, as in "1st", "2nd" and "3rd"
+    ordinal: fn(u64) -> &'static str,
+    strings: &'static [(&'static str, &'static str)],
+}
+
+impl Table {
+    fn get(&self, key: &str) -> Option<&'static str> {
+        self.strings
+            .iter()
+            .find(|(k, _)| *k == key)
+            .map(|(_, v)| *v)
+    }
+}
+
+fn one_other(n: u64) -> &'static str {
+    if n == 1 {
+        "one"
+    } else {
+        "other"
+    }
+}
+
+fn french_plural(n: u64) -> &'static str {
+    if n <= 1 {
+        "one"
+    } else {
+        "other"
+    }
+}
+
+fn russian_plural(n: u64) -> &'static str {
+    match (n % 10, n % 100) {
+        (1, r) if r != 11 => "one",
+        (2..=4, r) if !(12..=14).contains(&r) => "few",
+        _ => "many",
+    }
+}
+
+fn english_ordinal(n: u64) -> &'static str {
+    match (n % 10, n % 100) {
+        (1, r) if r != 11 => "one",
+        (2, r) if r != 12 => "two",
+        (3, r) if r != 13 => "few",
+        _ => "other",
+    }
+}
+
+fn french_ordinal(n: u64) -> &'static str {
+    if n == 1 {
+        "one"
+    } else {
+        "other"
+    }
+}
+
+fn no_ordinal(_: u64) -> &'static str {
+    "other"
+}
+
+static EN: Table = Table {
+    lang: "en",
+    plural: one_other,
+    ordinal: english_ordinal,
+    strings: &[
+        ("greeting_morning", "Good morning"),
+        ("greeting_afternoon", "Good afternoon"),
+        ("greeting_evening", "Good evening"),
+        ("greeting_night", "Good night"),
+        ("greeting_birthday", "Happy birthday"),
+        ("updates_zero", "Up to date"),
+        ("updates_one", "{count} update"),
+        ("updates_other", "{count} updates"),
+        ("packages_zero", "No packages"),
+        ("packages_one", "{count} package"),
+        ("packages_other", "{count} packages"),
+        ("processes_one", "{count} running process"),
+        ("processes_other", "{count} running processes"),
+        ("usage_used_first", "{used} Used / {total} ({free} Free)"),
+        ("usage_free_first", "{free} Free / {total} ({used} Used)"),
+        ("usage_mount", "{mount}: {used} / {total} ({percent}%)"),
+        ("battery", "{percent}% ({state})"),
+        ("battery_time", "{percent}% ({state}, {time} left)"),
+        (
+            "battery_time_charging",
+            "{percent}% ({state}, full in {time})",
+        ),
+        ("battery_charging", "charging"),
+        ("battery_discharging", "discharging"),
+        ("battery_full", "full"),
+        ("battery_not_charging", "not charging"),
+        ("battery_unknown", "unknown"),
+        ("ac_online", "on AC"),
+        ("ac_offline", "on battery"),
+        ("timed_out", "{module} timed out"),
+        ("unknown", "Unknown"),
+        ("date", "{month} {day}"),
+        ("ordinal_one", "{day}st"),
+        ("ordinal_two", "{day}nd"),
+        ("ordinal_few", "{day}rd"),
+        ("ordinal_other", "{day}th"),
+        ("month_1", "January"),
+        ("month_2", "February"),
+        ("month_3", "March"),
+        ("month_4", "April"),
+        ("month_5", "May"),
+        ("month_6", "June"),
+        ("month_7", "July"),
+        ("month_8", "August"),
+        ("month_9", "September"),
+        ("month_10", "October"),
+        ("month_11", "November"),
+        ("month_12", "December"),
+        ("weekday_1", "Monday"),
+        ("weekday_2", "Tuesday"),
+        ("weekday_3", "Wednesday"),
+        ("weekday_4", "Thursday"),
+        ("weekday_5", "Friday"),
+        ("weekday_6", "Saturday"),
+        ("weekday_7", "Sunday"),
+        ("label_hostname", "Host"),
+        ("label_greeting", "Greeting"),
+        ("label_datetime", "Date"),
+        ("label_weather", "Weather"),
+        ("label_release", "OS"),
+        ("label_kernel", "Kernel"),
+        ("label_memory", "Memory"),
+        ("label_swap", "Swap"),
+        ("label_disk", "Disk"),
+        ("label_environment", "DE"),
+        ("label_uptime", "Uptime"),
+        ("label_load", "Load"),
+        ("label_processes", "Processes"),
+        ("label_cpu", "CPU"),
+        ("label_cpu_usage", "CPU usage"),
+        ("label_battery", "Battery"),
+        ("label_updates", "Updates"),
+        ("label_packages", "Packages"),
+        ("label_song", "Song"),
+        ("label_fortune", "Fortune"),
+    ],
+};
+
+static DE: Table = Table {
+    lang: "de",
+    plural: one_other,
+    ordinal: no_ordinal,
+    strings: &[
+        ("greeting_morning", "Guten Morgen"),
+        ("greeting_afternoon", "Guten Tag"),
+        ("greeting_evening", "Guten Abend"),
+        ("greeting_night", "Gute Nacht"),
+        ("greeting_birthday", "Alles Gute zum Geburtstag"),
+        ("updates_zero", "Aktuell"),
+        ("updates_one", "{count} Update"),
+        ("updates_other", "{count} Updates"),
+        ("packages_zero", "Keine Pakete"),
+        ("packages_one", "{count} Paket"),
+        ("packages_other", "{count} Pakete"),
+        ("processes_one", "{count} laufender Prozess"),
+        ("processes_other", "{count} laufende Prozesse"),
+        ("usage_used_first", "{used} belegt / {total} ({free} frei)"),
+        ("usage_free_first", "{free} frei / {total} ({used} belegt)"),
+        ("usage_mount", "{mount}: {used} / {total} ({percent} %)"),
+        ("battery", "{percent} % ({state})"),
+        ("battery_time", "{percent} % ({state}, noch {time})"),
+        (
+            "battery_time_charging",
+            "{percent} % ({state}, voll in {time})",
+        ),
+        ("battery_charging", "lädt"),
+        ("battery_discharging", "entlädt"),
+        ("battery_full", "voll"),
+        ("battery_not_charging", "lädt nicht"),
+        ("battery_unknown", "unbekannt"),
+        ("ac_online", "am Netz"),
+        ("ac_offline", "im Akkubetrieb"),
+        ("timed_out", "{module}: Zeitüberschreitung"),
+        ("unknown", "Unbekannt"),
+        ("date", "{day} {month}"),
+        ("ordinal_other", "{day}."),
+        ("month_1", "Januar"),
+        ("month_2", "Februar"),
+        ("month_3", "März"),
+        ("month_4", "April"),
+        ("month_5", "Mai"),
+        ("month_6", "Juni"),
+        ("month_7", "Juli"),
+        ("month_8", "August"),
+        ("month_9", "September"),
+        ("month_10", "Oktober"),
+        ("month_11", "November"),
+        ("month_12", "Dezember"),
+        ("weekday_1", "Montag"),
+        ("weekday_2", "Dienstag"),
+        ("weekday_3", "Mittwoch"),
+        ("weekday_4", "Donnerstag"),
+        ("weekday_5", "Freitag"),
+        ("weekday_6", "Samstag"),
+        ("weekday_7", "Sonntag"),
+        ("label_hostname", "Host"),
+        ("label_greeting", "Begrüßung"),
+        ("label_datetime", "Datum"),
+        ("label_weather", "Wetter"),
+        ("label_release", "OS"),
+        ("label_kernel", "Kernel"),
+        ("label_memory", "Speicher"),
+        ("label_swap", "Swap"),
+        ("label_disk", "Festplatte"),
+        ("label_environment", "DE"),
+        ("label_uptime", "Laufzeit"),
+        ("label_load", "Last"),
+        ("label_processes", "Prozesse"),
+        ("label_cpu", "CPU"),
+        ("label_cpu_usage", "CPU-Last"),
+        ("label_battery", "Akku"),
+        ("label_updates", "Updates"),
+        ("label_packages", "Pakete"),
+        ("label_song", "Musik"),
+        ("label_fortune", "Spruch"),
+    ],
+};
+
+static FR: Table = Table {
+    lang: "fr",
+    plural: french_plural,
+    ordinal: french_ordinal,
+    strings: &[
+        ("greeting_morning", "Bonjour"),
+        ("greeting_afternoon", "Bon après-midi"),
+        ("greeting_evening", "Bonsoir"),
+        ("greeting_night", "Bonne nuit"),
+        ("greeting_birthday", "Joyeux anniversaire"),
+        ("updates_zero", "À jour"),
+        ("updates_one", "{count} mise à jour"),
+        ("updates_other", "{count} mises à jour"),
+        ("packages_zero", "Aucun paquet"),
+        ("packages_one", "{count} paquet"),
+        ("packages_other", "{count} paquets"),
+        ("processes_one", "{count} processus actif"),
+        ("processes_other", "{count} processus actifs"),
+        (
+            "usage_used_first",
+            "{used} utilisés / {total} ({free} libres)",
+        ),
+        (
+            "usage_free_first",
+            "{free} libres / {total} ({used} utilisés)",
+        ),
+        ("usage_mount", "{mount} : {used} / {total} ({percent} %)"),
+        ("battery", "{percent} % ({state})"),
+        ("battery_time", "{percent} % ({state}, {time} restantes)"),
+        (
+            "battery_time_charging",
+            "{percent} % ({state}, pleine dans {time})",
+        ),
+        ("battery_charging", "en charge"),
+        ("battery_discharging", "en décharge"),
+        ("battery_full", "pleine"),
+        ("battery_not_charging", "ne charge pas"),
+        ("battery_unknown", "inconnu"),
+        ("ac_online", "sur secteur"),
+        ("ac_offline", "sur batterie"),
+        ("timed_out", "{module} : délai dépassé"),
+        ("unknown", "Inconnu"),
+        ("date", "{day} {month}"),
+        ("ordinal_one", "{day}er"),
+        ("ordinal_other", "{day}"),
+        ("month_1", "janvier"),
+        ("month_2", "février"),
+        ("month_3", "mars"),
+        ("month_4", "avril"),
+        ("month_5", "mai"),
+        ("month_6", "juin"),
+        ("month_7", "juillet"),
+        ("month_8", "août"),
+        ("month_9", "septembre"),
+        ("month_10", "octobre"),
+        ("month_11", "novembre"),
+        ("month_12", "décembre"),
+        ("weekday_1", "lundi"),
+        ("weekday_2", "mardi"),
+        ("weekday_3", "mercredi"),
+        ("weekday_4", "jeudi"),
+        ("weekday_5", "vendredi"),
+        ("weekday_6", "samedi"),
+        ("weekday_7", "dimanche"),
+        ("label_hostname", "Hôte"),
+        ("label_greeting", "Salutation"),
+        ("label_datetime", "Date"),
+        ("label_weather", "Météo"),
+        ("label_release", "OS"),
+        ("label_kernel", "Noyau"),
+        ("label_memory", "Mémoire"),
+        ("label_swap", "Swap"),
+        ("label_disk", "Disque"),
+        ("label_environment", "Bureau"),
+        ("label_uptime", "Durée"),
+        ("label_load", "Charge"),
+        ("label_processes", "Processus"),
+        ("label_cpu", "CPU"),
+        ("label_cpu_usage", "Charge CPU"),
+        ("label_battery", "Batterie"),
+        ("label_updates", "Mises à jour"),
+        ("label_packages", "Paquets"),
+        ("label_song", "Musique"),
+        ("label_fortune", "Citation"),
+    ],
+};
+
+static ES: Table = Table {
+    lang: "es",
+    plural: one_other,
+    ordinal: no_ordinal,
+    strings: &[
+        ("greeting_morning", "Buenos días"),
+        ("greeting_afternoon", "Buenas tardes"),
+        ("greeting_evening", "Buenas noches"),
+        ("greeting_night", "Buenas noches"),
+        ("greeting_birthday", "Feliz cumpleaños"),
+        ("updates_zero", "Actualizado"),
+        ("updates_one", "{count} actualización"),
+        ("updates_other", "{count} actualizaciones"),
+        ("packages_zero", "Ningún paquete"),
+        ("packages_one", "{count} paquete"),
+        ("packages_other", "{count} paquetes"),
+        ("processes_one", "{count} proceso en ejecución"),
+        ("processes_other", "{count} procesos en ejecución"),
+        (
+            "usage_used_first",
+            "{used} usados / {total} ({free} libres)",
+        ),
+        (
+            "usage_free_first",
+            "{free} libres / {total} ({used} usados)",
+        ),
+        ("usage_mount", "{mount}: {used} / {total} ({percent} %)"),
+        ("battery", "{percent} % ({state})"),
+        ("battery_time", "{percent} % ({state}, quedan {time})"),
+        (
+            "battery_time_charging",
+            "{percent} % ({state}, llena en {time})",
+        ),
+        ("battery_charging", "cargando"),
+        ("battery_discharging", "descargando"),
+        ("battery_full", "llena"),
+        ("battery_not_charging", "sin cargar"),
+        ("battery_unknown", "desconocido"),
+        ("ac_online", "con corriente"),
+        ("ac_offline", "con batería"),
+        ("timed_out", "{module}: tiempo agotado"),
+        ("unknown", "Desconocido"),
+        ("date", "{day} de {month}"),
+        ("ordinal_other", "{day}"),
+        ("month_1", "enero"),
+        ("month_2", "febrero"),
+        ("month_3", "marzo"),
+        ("month_4", "abril"),
+        ("month_5", "mayo"),
+        ("month_6", "junio"),
+        ("month_7", "julio"),
+        ("month_8", "agosto"),
+        ("month_9", "septiembre"),
+        ("month_10", "octubre"),
+        ("month_11", "noviembre"),
+        ("month_12", "diciembre"),
+        ("weekday_1", "lunes"),
+        ("weekday_2", "martes"),
+        ("weekday_3", "miércoles"),
+        ("weekday_4", "jueves"),
+        ("weekday_5", "viernes"),
+        ("weekday_6", "sábado"),
+        ("weekday_7", "domingo"),
+        ("label_hostname", "Host"),
+        ("label_greeting", "Saludo"),
+        ("label_datetime", "Fecha"),
+        ("label_weather", "Tiempo"),
+        ("label_release", "SO"),
+        ("label_kernel", "Kernel"),
+        ("label_memory", "Memoria"),
+        ("label_swap", "Swap"),
+        ("label_disk", "Disco"),
+        ("label_environment", "Escritorio"),
+        ("label_uptime", "Tiempo activo"),
+        ("label_load", "Carga"),
+        ("label_processes", "Procesos"),
+        ("label_cpu", "CPU"),
+        ("label_cpu_usage", "Uso de CPU"),
+        ("label_battery", "Batería"),
+        ("label_updates", "Actualizaciones"),
+        ("label_packages", "Paquetes"),
+        ("label_song", "Música"),
+        ("label_fortune", "Cita"),
+    ],
+};
+
+static RU: Table = Table {
+    lang: "ru",
+    plural: russian_plural,
+    ordinal: no_ordinal,
+    strings: &[
+        ("greeting_morning", "Доброе утро"),
+        ("greeting_afternoon", "Добрый день"),
+        ("greeting_evening", "Добрый вечер"),
+        ("greeting_night", "Доброй ночи"),
+        ("greeting_birthday", "С днём рождения"),
+        ("updates_zero", "Обновлений нет"),
+        ("updates_one", "{count} обновление"),
+        ("updates_few", "{count} обновления"),
+        ("updates_many", "{count} обновлений"),
+        ("packages_zero", "Нет пакетов"),
+        ("packages_one", "{count} пакет"),
+        ("packages_few", "{count} пакета"),
+        ("packages_many", "{count} пакетов"),
+        ("processes_one", "{count} работающий процесс"),
+        ("processes_few", "{count} работающих процесса"),
+        ("processes_many", "{count} работающих процессов"),
+        (
+            "usage_used_first",
+            "{used} занято / {total} ({free} свободно)",
+        ),
+        (
+            "usage_free_first",
+            "{free} свободно / {total} ({used} занято)",
+        ),
+        ("usage_mount", "{mount}: {used} / {total} ({percent}%)"),
+        ("battery", "{percent}% ({state})"),
+        ("battery_time", "{percent}% ({state}, осталось {time})"),
+        (
+            "battery_time_charging",
+            "{percent}% ({state}, до полной {time})",
+        ),
+        ("battery_charging", "заряжается"),
+        ("battery_discharging", "разряжается"),
+        ("battery_full", "заряжена"),
+        ("battery_not_charging", "не заряжается"),
+        ("battery_unknown", "неизвестно"),
+        ("ac_online", "от сети"),
+        ("ac_offline", "от батареи"),
+        ("timed_out", "{module}: превышено время ожидания"),
+        ("unknown", "Неизвестно"),
+        ("date", "{day} {month}"),
+        ("ordinal_other", "{day}"),
+        // Months are used in dates, so they are in the genitive case
+        ("month_1", "января"),
+        ("month_2", "февраля"),
+        ("month_3", "марта"),
+        ("month_4", "апреля"),
+        ("month_5", "мая"),
+        ("month_6", "июня"),
+        ("month_7", "июля"),
+        ("month_8", "августа"),
+        ("month_9", "сентября"),
+        ("month_10", "октября"),
+        ("month_11", "ноября"),
+        ("month_12", "декабря"),
+        ("weekday_1", "понедельник"),
+        ("weekday_2", "вторник"),
+        ("weekday_3", "среда"),
+        ("weekday_4", "четверг"),
+        ("weekday_5", "пятница"),
+        ("weekday_6", "суббота"),
+        ("weekday_7", "воскресенье"),
+        ("label_hostname", "Хост"),
+        ("label_greeting", "Приветствие"),
+        ("label_datetime", "Дата"),
+        ("label_weather", "Погода"),
+        ("label_release", "ОС"),
+        ("label_kernel", "Ядро"),
+        ("label_memory", "Память"),
+        ("label_swap", "Подкачка"),
+        ("label_disk", "Диск"),
+        ("label_environment", "Окружение"),
+        ("label_uptime", "Время работы"),
+        ("label_load", "Нагрузка"),
+        ("label_processes", "Процессы"),
+        ("label_cpu", "ЦП"),
+        ("label_cpu_usage", "Загрузка ЦП"),
+        ("label_battery", "Батарея"),
+        ("label_updates", "Обновления"),
+        ("label_packages", "Пакеты"),
+        ("label_song", "Музыка"),
+        ("label_fortune", "Цитата"),
+    ],
+};
+
+static TABLES: [&Table; 5] = [&EN, &DE, &FR, &ES, &RU];
+
+/// The language of the user's locale, from `LC_ALL`, `LC_MESSAGES` or `LANG`
+fn system_lang() -> Option<String> {
+    let locale = ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|name| env::var(name).ok())
+        .find(|value| !value.is_empty())?;
+
+    // de_DE.UTF-8@euro -> de
+    let lang = locale.split(['_', '.', '@']).next()?.to_lowercase();
+    Some(lang)
+}
+
+/// The strings of the configured language, with `[locale.strings]` on top.
+/// Strings missing from a language fall back to English.
+pub(crate) struct Translations {
+    table: &'static Table,
+    overrides: &'static BTreeMap<String, String>,
+}
+
+impl Translations {
+    pub(crate) fn new(locale: &'static Locale) -> Translations {
+        let lang = locale.lang.clone().or_else(system_lang);
+        let table = match lang.as_deref() {
+            // The C and POSIX locales are English
+            None | Some("c" | "posix") => &EN,
+            Some(lang) => TABLES
+                .iter()
+                .find(|t| t.lang == lang)
+                .copied()
+                .unwrap_or_else(|| {
+                    tracing::info!("No translation for {}, using English", lang);
+                    &EN
+                }),
+        };
+
+        Translations {
+            table,
+            overrides: &locale.strings,
+        }
+    }
+
+    /// The string for `key`, if there is one
+    pub(crate) fn lookup(&self, key: &str) -> Option<String> {
+        match self.overrides.get(key) {
+            Some(value) => Some(value.clone()),
+            None => self
+                .table
+                .get(key)
+                .or_else(|| EN.get(key))
+                .map(ToString::to_string),
+        }
+    }
+
+    pub(crate) fn get(&self, key: &str) -> String {
+        self.lookup(key).unwrap_or_else(|| key.to_string())
+    }
+
+    /// `count` of `noun`, like "3 updates", in the right plural form
+    pub(crate) fn count(&self, noun: &str, count: u64) -> String {
+        let category = (self.table.plural)(count);
+        let template = Some(format!("{}_zero", noun))
+            .filter(|_| count == 0)
+            .and_then(|key| self.lookup(&key))
+            .or_else(|| self.lookup(&format!("{}_{}", noun, category)))
+            .unwrap_or_else(|| self.get(&format!("{}_other", noun)));
+
+        fill(&template, &[("count", count.to_string())])
+    }
+
+    /// The day of the month as an ordinal number, like "1st" or "1."
+    pub(crate) fn ordinal(&self, day: u32) -> String {
+        let category = (self.table.ordinal)(day.into());
+        let template = self
+            .lookup(&format!("ordinal_{}", category))
+            .unwrap_or_else(|| self.get("ordinal_other"));
+
+        fill(&template, &[("day", day.to_string())])
+    }
+
+    /// The name of a month, counting from 1
+    pub(crate) fn month(&self, month: u32) -> String {
+        self.get(&format!("month_{}", month))
+    }
+
+    /// The name of a weekday, counting from Monday as 1
+    pub(crate) fn weekday(&self, weekday: u32) -> String {
+        self.get(&format!("weekday_{}", weekday))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static NO_OVERRIDES: BTreeMap<String, String> = BTreeMap::new();
+
+    fn translations(table: &'static Table) -> Translations {
+        Translations {
+            table,
+            overrides: &NO_OVERRIDES,
+        }
+    }
+
+    #[test]
+    fn russian_plurals() {
+        assert_eq!(russian_plural(1), "one");
+        assert_eq!(russian_plural(2), "few");
+        assert_eq!(russian_plural(5), "many");
+        assert_eq!(russian_plural(11), "many");
+        assert_eq!(russian_plural(21), "one");
+
+        let ru = translations(&RU);
+        assert_eq!(ru.count("packages", 1), "1 пакет");
+        assert_eq!(ru.count("packages", 2), "2 пакета");
+        assert_eq!(ru.count("packages", 5), "5 пакетов");
+        assert_eq!(ru.count("packages", 11), "11 пакетов");
+        assert_eq!(ru.count("packages", 21), "21 пакет");
+    }
+
+    #[test]
+    fn english_ordinals() {
+        let en = translations(&EN);
+        let ordinals: Vec<String> = [1, 2, 3, 4, 11, 12, 13, 21, 22, 23]
+            .iter()
+            .map(|day| en.ordinal(*day))
+            .collect();
+        assert_eq!(
+            ordinals,
+            ["1st", "2nd", "3rd", "4th", "11th", "12th", "13th", "21st", "22nd", "23rd"]
+        );
+    }
+
+    #[test]
+    fn zero_form_comes_first() {
+        let en = translations(&EN);
+        assert_eq!(en.count("updates", 0), "Up to date");
+        assert_eq!(en.count("updates", 1), "1 update");
+        assert_eq!(en.count("updates", 2), "2 updates");
+    }
+}
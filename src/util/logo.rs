@@ -0,0 +1,191 @@
+use {
+    crate::util::{
+        config::expand_home,
+        formatting::strip_ansi,
+        statics::{COLOR, CONF},
+        theme::{color, paint},
+    },
+    std::fs,
+    sys_info::linux_os_release,
+};
+
+/// A built-in logo, used for distros whose `ID` or `ID_LIKE` is one of `ids`
+struct Builtin {
+    ids: &'static [&'static str],
+    color: &'static str,
+    art: &'static str,
+}
+
+static BUILTINS: [Builtin; 8] = [
+    Builtin {
+        ids: &["arch", "archlinux"],
+        color: "cyan",
+        art: r"      /\
+     /  \
+    /\   \
+   /      \
+  /   ,,   \
+ /   |  |  -\
+/_-''    ''-_\",
+    },
+    Builtin {
+        ids: &["debian"],
+        color: "red",
+        art: r"  _____
+ /  __ \
+|  /    |
+|  \___-
+-_
+  --_",
+    },
+    Builtin {
+        ids: &["ubuntu"],
+        color: "208",
+        art: r"         _
+     ---(_)
+ _/  ---  \
+(_) |   |
+  \  --- _/
+     ---(_)",
+    },
+    Builtin {
+        ids: &["fedora", "rhel"],
+        color: "blue",
+        art: r"      _____
+     /   __)\
+     |  /  \ \
+  ___|  |__/ /
+ / (_    _)_/
+/ /  |  |
+\ \__/  |
+ \(_____/",
+    },
+    Builtin {
+        ids: &["void"],
+        color: "green",
+        art: r"    _______
+ _ \______ -
+| \  ___  \ |
+| | /   \ | |
+| | \___/ | |
+| \______ \_|
+ -_______\",
+    },
+    Builtin {
+        ids: &["alpine"],
+        color: "blue",
+        art: r"   /\ /\
+  // \  \
+ //   \  \
+///    \  \
+//      \  \
+         \",
+    },
+    Builtin {
+        ids: &["nixos"],
+        color: "bright_blue",
+        art: r"  \\  \\ //
+ ==\\__\\/ //
+   //   \\//
+==//     //==
+ //\\___//
+// /\\  \\==
+  // \\  \\",
+    },
+    Builtin {
+        ids: &["gentoo"],
+        color: "magenta",
+        art: r" _-----_
+(       \
+\    0   \
+ \        )
+ /      _/
+(     _-
+\____-",
+    },
+];
+
+/// Shown when the distro has no logo of its own
+static TUX: &str = r"    .--.
+   |o_o |
+   |:_/ |
+  //   \ \
+ (|     | )
+/'\_   _/`\
+\___)=(___/";
+
+/// The lines of the logo in `[logo] path`, which may contain ANSI colors
+fn read_logo(path: &str) -> Option<Vec<String>> {
+    let path = expand_home(path);
+    let content = fs::read_to_string(&path)
+        .map_err(|e| tracing::warn!("Could not read logo {}: {}", path.display(), e))
+        .ok()?;
+
+    let lines = content
+        .lines()
+        .map(|line| {
+            let line = line.replace('\t', "    ");
+            if !*COLOR {
+                strip_ansi(&line)
+            } else if line.contains('\x1b') {
+                // Keep colors that are left on from running into the box
+                format!("{}\x1b[0m", line)
+            } else {
+                line
+            }
+        })
+        .collect();
+
+    Some(lines)
+}
+
+/// The `ID` and `ID_LIKE` values of the running distro, most specific first
+fn distro_ids() -> Vec<String> {
+    let release = match linux_os_release() {
+        Ok(release) => release,
+        Err(_) => return Vec::new(),
+    };
+
+    release
+        .id
+        .into_iter()
+        .chain(release.id_like)
+        .flat_map(|ids| {
+            ids.trim_matches('"')
+                .split_whitespace()
+                .map(str::to_lowercase)
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// The built-in logo picked by `[logo] name`, or by the distro if that is not set
+fn builtin_logo() -> Vec<String> {
+    let ids = match &CONF.logo.name {
+        Some(name) => vec![name.to_lowercase()],
+        None => distro_ids(),
+    };
+    let builtin = ids
+        .iter()
+        .find_map(|id| BUILTINS.iter().find(|b| b.ids.contains(&id.as_str())));
+
+    let (art, default_color) = match builtin {
+        Some(builtin) => (builtin.art, Some(builtin.color)),
+        None => (TUX, None),
+    };
+    let color = color(CONF.logo.color.as_deref(), default_color);
+
+    art.lines()
+        .map(|line| paint(color.as_ref(), line))
+        .collect()
+}
+
+/// The lines of the logo to show beside the box, if `[logo]` is enabled
+pub(crate) fn load_logo() -> Option<Vec<String>> {
+    if !CONF.logo.enabled {
+        return None;
+    }
+
+    let logo = CONF.logo.path.as_deref().and_then(read_logo);
+    Some(logo.unwrap_or_else(builtin_logo))
+}
@@ -1,4 +1,14 @@
+pub mod cache;
+pub mod commands;
 pub mod conf_structs;
 pub mod config;
 pub mod formatting;
+pub mod gauge;
+pub mod locale;
+pub mod logo;
+pub mod module;
+pub mod report;
 pub mod statics;
+pub mod template;
+pub mod theme;
+pub mod timeout;
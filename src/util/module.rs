@@ -0,0 +1,143 @@
+use crate::util::{
+    conf_structs::{LabelAlign, Overflow, Style},
+    formatting::{calc_whitespace, display_width, fit, upper_first},
+    gauge::Gauge,
+    statics::{CONF, LOCALE, THEME, WIDTH},
+    theme::paint,
+};
+
+/// Names of the built-in modules that can be listed in `[layout] modules`
+pub(crate) static MODULE_NAMES: [&str; 20] = [
+    "hostname",
+    "greeting",
+    "datetime",
+    "weather",
+    "release",
+    "kernel",
+    "memory",
+    "swap",
+    "disk",
+    "environment",
+    "uptime",
+    "load",
+    "processes",
+    "cpu",
+    "cpu_usage",
+    "battery",
+    "updates",
+    "packages",
+    "song",
+    "fortune",
+];
+
+/// A piece of information that can be shown as a row inside the box
+pub(crate) trait Module {
+    /// The name used to refer to this module in `[layout] modules`
+    fn name(&self) -> &'static str;
+
+    /// The icon shown in front of the text, if icons are enabled
+    fn icon(&self) -> Option<&'static str> {
+        None
+    }
+
+    /// The text of the row, without icon or borders
+    fn text(&self) -> String;
+
+    /// The label shown in front of the text with `[layout] style = "labelled"`
+    fn label(&self) -> String {
+        match CONF.layout.labels.get(self.name()) {
+            Some(label) => label.clone(),
+            None => default_label(self.name()),
+        }
+    }
+
+    /// The percentage behind the text, for modules that can show a usage bar
+    /// and change color at thresholds
+    fn gauge(&self) -> Option<Gauge> {
+        None
+    }
+
+    /// What to do with text that does not fit into the box
+    fn overflow(&self) -> Overflow {
+        CONF.layout.overflow(self.name())
+    }
+
+    /// The rows of this module, including borders and padding. Text that does
+    /// not fit is handled by the module's overflow policy, wrapped lines are
+    /// indented to line up with the first one. Labels are padded to
+    /// `label_width` so that they line up with those of the other modules.
+    fn render(&self, label_width: usize) -> Vec<String> {
+        let color = THEME.label(self.name());
+        let mut prefix = String::new();
+        let mut indent = 0;
+
+        match self.icon() {
+            Some(icon) => {
+                prefix.push_str(&format!("{} ", paint(color, icon)));
+                indent += display_width(icon) + 1;
+            }
+            // Leave the icon's space empty, so the text lines up with the other rows
+            None if CONF.icons.enabled => {
+                let width = if CONF.icons.kind == "emoji" { 2 } else { 1 };
+                prefix.push_str(&" ".repeat(width + 1));
+                indent += width + 1;
+            }
+            None => (),
+        }
+        if CONF.layout.style == Style::Labelled {
+            let label = self.label();
+            let padding = " ".repeat(label_width.saturating_sub(display_width(&label)));
+            let label = format!("{}{}", label, CONF.layout.separator);
+            let label = match CONF.layout.label_align {
+                LabelAlign::Right => format!("{}{}", padding, paint(color, &label)),
+                LabelAlign::Left => format!("{}{}", paint(color, &label), padding),
+            };
+            prefix.push_str(&format!("{} ", label));
+            indent += label_width + display_width(&CONF.layout.separator) + 1;
+        }
+
+        let columns = WIDTH.saturating_sub(4 + indent);
+        let mut lines = fit(&self.text(), columns, self.overflow());
+        let mut value_color = THEME.value(self.name());
+        if let Some(gauge) = self.gauge() {
+            if gauge.bar {
+                lines = gauge.prepend_bar(lines, columns);
+            }
+            value_color = gauge.color().or(value_color);
+        }
+
+        lines
+            .into_iter()
+            .enumerate()
+            .map(|(i, line)| {
+                let text = paint(value_color, &line);
+                if i == 0 {
+                    format!("{}{}", prefix, text)
+                } else {
+                    format!("{}{}", " ".repeat(indent), text)
+                }
+            })
+            .map(calc_whitespace)
+            .collect()
+    }
+}
+
+/// The label of a module when `[layout.labels]` does not set one
+fn default_label(module: &str) -> String {
+    LOCALE
+        .lookup(&format!("label_{}", module))
+        .unwrap_or_else(|| upper_first(module.to_string()))
+}
+
+/// Picks the icon matching `[icons] kind`, or nothing if icons are disabled
+pub(crate) fn pick_icon(normal: &'static str, emoji: &'static str) -> Option<&'static str> {
+    if !CONF.icons.enabled {
+        return None;
+    }
+
+    match CONF.icons.kind.as_str() {
+        "emoji" => Some(emoji),
+        "normal" => Some(normal),
+        _ => None,
+    }
+}
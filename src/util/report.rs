@@ -0,0 +1,76 @@
+use {
+    crate::{
+        funcs::{
+            battery::Battery,
+            custom::Custom,
+            fortune::Fortune,
+            greet::{Greeting, Hostname},
+            misc::{DateTime, Song, Weather},
+            pkgs::{Packages, Updates},
+            system_info::{
+                Cpu, CpuUsage, Disk, Environment, Kernel, Load, Memory, Processes, Release, Swap,
+                Uptime,
+            },
+        },
+        util::config::Format,
+    },
+    serde::Serialize,
+    std::{
+        io::{stdout, ErrorKind, Write},
+        process::exit,
+    },
+};
+
+/// The raw values of every module, for `--format json` and `--format toml`
+#[derive(Serialize)]
+pub(crate) struct Report<'a> {
+    pub(crate) hostname: &'a Option<Hostname>,
+    pub(crate) greeting: &'a Option<Greeting>,
+    pub(crate) datetime: &'a Option<DateTime>,
+    pub(crate) weather: &'a Option<Weather>,
+    pub(crate) release: &'a Option<Release>,
+    pub(crate) kernel: &'a Option<Kernel>,
+    pub(crate) memory: &'a Option<Memory>,
+    pub(crate) swap: &'a Option<Swap>,
+    pub(crate) disk: &'a [Disk],
+    pub(crate) environment: &'a Option<Environment>,
+    pub(crate) uptime: &'a Option<Uptime>,
+    pub(crate) load: &'a Option<Load>,
+    pub(crate) processes: &'a Option<Processes>,
+    pub(crate) cpu: &'a Option<Cpu>,
+    pub(crate) cpu_usage: &'a Option<CpuUsage>,
+    pub(crate) battery: &'a Option<Battery>,
+    pub(crate) updates: &'a Option<Updates>,
+    pub(crate) packages: &'a Option<Packages>,
+    pub(crate) song: &'a Option<Song>,
+    pub(crate) fortune: &'a Option<Fortune>,
+    pub(crate) custom: &'a [Custom],
+}
+
+impl Report<'_> {
+    pub(crate) fn print(&self, format: Format) {
+        let output = match format {
+            Format::Json => serde_json::to_string_pretty(self).map_err(|e| e.to_string()),
+            // Going through `toml::Value` puts plain values before tables, which TOML requires
+            Format::Toml => toml::Value::try_from(self)
+                .and_then(|value| toml::to_string(&value))
+                .map_err(|e| e.to_string()),
+            Format::Box => return,
+        };
+
+        let output = match output {
+            Ok(output) => output,
+            Err(e) => {
+                tracing::error!("Could not serialize output: {}", e);
+                return;
+            }
+        };
+
+        // Status bars and pipes like `| head -1` may stop reading early, which is fine
+        match writeln!(stdout().lock(), "{}", output) {
+            Err(e) if e.kind() == ErrorKind::BrokenPipe => exit(0),
+            Err(e) => tracing::error!("Could not write output: {}", e),
+            Ok(()) => (),
+        }
+    }
+}
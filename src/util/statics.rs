@@ -1,9 +1,23 @@
 use {
-    crate::util::{conf_structs::Config, config::read_config},
+    crate::util::{
+        conf_structs::Config,
+        config::{parse_args, read_config, Args},
+        formatting::box_width,
+        locale::Translations,
+        logo::load_logo,
+        theme::{wants_color, Palette},
+    },
     once_cell::sync::Lazy,
+    std::io::stdout,
 };
 
+pub(crate) static ARGS: Lazy<Args> = Lazy::new(parse_args);
 pub(crate) static CONF: Lazy<Config> = Lazy::new(read_config);
+pub(crate) static COLOR: Lazy<bool> = Lazy::new(|| wants_color(&stdout()));
+pub(crate) static LOCALE: Lazy<Translations> = Lazy::new(|| Translations::new(&CONF.locale));
+pub(crate) static THEME: Lazy<Palette> = Lazy::new(|| Palette::new(&CONF.theme));
+pub(crate) static LOGO: Lazy<Option<Vec<String>>> = Lazy::new(load_logo);
+pub(crate) static WIDTH: Lazy<usize> = Lazy::new(box_width);
 
 pub(crate) static GREET_ICONS: [&str; 4] = ["", "", "", ""];
 pub(crate) static GREET_EMOJIS: [&str; 4] = ["🌇", "🏙️", "🌆", "🌃"];
@@ -25,5 +39,11 @@ pub(crate) static PACKAGE_ICONS: [&str; 12] =
 pub(crate) static PACKAGE_EMOJIS: [&str; 13] = [
     "☑️", "1️⃣", "2️⃣", "3️⃣", "4️⃣", "5️⃣", "6️⃣", "7️⃣", "8️⃣", "9️⃣", "🔟", "‼️", "📦",
 ];
-pub(crate) static MISC_ICONS: [&str; 6] = ["", "", "", "", "", ""];
-pub(crate) static MISC_EMOJIS: [&str; 6] = ["💻", "🫀", "🧠", "💾", "🖥️", "🎵"];
+pub(crate) static MISC_ICONS: [&str; 13] = [
+    "", "", "", "", "", "", "", "", "", "", "", "", "",
+];
+pub(crate) static MISC_EMOJIS: [&str; 13] = [
+    "💻", "🫀", "🧠", "💾", "🖥️", "🎵", "🥠", "⏱️", "📈", "⚙️", "🧮", "🔥", "🔄",
+];
+pub(crate) static BATTERY_ICONS: [&str; 6] = ["", "", "", "", "", ""];
+pub(crate) static BATTERY_EMOJIS: [&str; 6] = ["🪫", "🔋", "🔋", "🔋", "🔋", "🔌"];
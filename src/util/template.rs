@@ -0,0 +1,71 @@
+/// Fills the `{name}` placeholders of a `format` template with `values`.
+/// `{{` and `}}` stand for literal braces, and placeholders that have no
+/// value are kept as they are.
+pub(crate) fn fill(template: &str, values: &[(&str, String)]) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(i) = rest.find(['{', '}']) {
+        out.push_str(&rest[..i]);
+        rest = &rest[i..];
+
+        if rest.starts_with("{{") || rest.starts_with("}}") {
+            out.push_str(&rest[..1]);
+            rest = &rest[2..];
+            continue;
+        }
+
+        let placeholder = rest
+            .strip_prefix('{')
+            .and_then(|r| r.find('}').map(|end| &r[..end]));
+        let value = placeholder.and_then(|name| values.iter().find(|(n, _)| *n == name));
+        match (placeholder, value) {
+            (Some(name), Some((_, value))) => {
+                out.push_str(value);
+                rest = &rest[name.len() + 2..];
+            }
+            _ => {
+                out.push_str(&rest[..1]);
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values() -> Vec<(&'static str, String)> {
+        vec![("name", "Tux".to_string()), ("count", "3".to_string())]
+    }
+
+    #[test]
+    fn fills_placeholders() {
+        assert_eq!(fill("{name} has {count}", &values()), "Tux has 3");
+        assert_eq!(fill("no placeholders", &values()), "no placeholders");
+    }
+
+    #[test]
+    fn doubled_braces_are_literal() {
+        assert_eq!(fill("{{name}} is {name}", &values()), "{name} is Tux");
+        assert_eq!(fill("}}{{", &values()), "}{");
+        assert_eq!(fill("{{{name}}}", &values()), "{Tux}");
+    }
+
+    #[test]
+    fn unknown_placeholders_are_kept() {
+        assert_eq!(fill("{nope} {name}", &values()), "{nope} Tux");
+        assert_eq!(fill("{}", &values()), "{}");
+    }
+
+    #[test]
+    fn unterminated_brace_is_kept() {
+        assert_eq!(fill("{name", &values()), "{name");
+        assert_eq!(fill("{name} {", &values()), "Tux {");
+        assert_eq!(fill("a } b", &values()), "a } b");
+    }
+}
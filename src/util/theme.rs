@@ -0,0 +1,296 @@
+use {
+    crate::util::{
+        conf_structs::{BorderStyle, Theme},
+        config::ColorChoice,
+        statics::{ARGS, COLOR},
+    },
+    std::{collections::BTreeMap, env, io::IsTerminal, str::FromStr},
+};
+
+/// A foreground color, as set in `[theme]`
+pub(crate) enum Color {
+    /// One of the 16 basic colors, stored as its SGR code
+    Named(u8),
+    /// One of the 256 indexed colors
+    Fixed(u8),
+    /// A truecolor value
+    Rgb(u8, u8, u8),
+}
+
+static COLOR_NAMES: [&str; 8] = [
+    "black", "red", "green", "yellow", "blue", "magenta", "cyan", "white",
+];
+
+impl FromStr for Color {
+    type Err = String;
+
+    /// Parses `red`, `bright_red`, `208` or `#ff8700`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim().to_lowercase();
+
+        if let Some(hex) = s.strip_prefix('#') {
+            let channel = |i: usize| {
+                hex.get(i..i + 2)
+                    .and_then(|c| u8::from_str_radix(c, 16).ok())
+            };
+            return match (hex.len(), channel(0), channel(2), channel(4)) {
+                (6, Some(r), Some(g), Some(b)) => Ok(Color::Rgb(r, g, b)),
+                _ => Err(format!("{} is not a #rrggbb color", s)),
+            };
+        }
+
+        if let Ok(index) = s.parse::<u8>() {
+            return Ok(Color::Fixed(index));
+        }
+
+        let (base, name) = match s.strip_prefix("bright_") {
+            Some(name) => (90, name),
+            None => (30, s.as_str()),
+        };
+        COLOR_NAMES
+            .iter()
+            .position(|n| *n == name)
+            .map(|i| Color::Named(base + i as u8))
+            .ok_or_else(|| format!("unknown color {}", s))
+    }
+}
+
+impl Color {
+    fn escape(&self) -> String {
+        match self {
+            Color::Named(code) => format!("\x1b[{}m", code),
+            Color::Fixed(index) => format!("\x1b[38;5;{}m", index),
+            Color::Rgb(r, g, b) => format!("\x1b[38;2;{};{};{}m", r, g, b),
+        }
+    }
+}
+
+/// Whether output to `stream` should be colored. `--color` wins, then `NO_COLOR`,
+/// `CLICOLOR_FORCE` and `TERM=dumb`, and otherwise only terminals get colors.
+pub(crate) fn wants_color(stream: &impl IsTerminal) -> bool {
+    let var = |name: &str| env::var(name).ok().filter(|v| !v.is_empty());
+
+    match ARGS.color {
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+        ColorChoice::Auto if var("NO_COLOR").is_some() => false,
+        ColorChoice::Auto if var("CLICOLOR_FORCE").is_some_and(|v| v != "0") => true,
+        ColorChoice::Auto => var("TERM").as_deref() != Some("dumb") && stream.is_terminal(),
+    }
+}
+
+/// Wraps `text` in the escape codes for `color`, if there is one and colors are on.
+/// Everything printed in color goes through here.
+pub(crate) fn paint(color: Option<&Color>, text: &str) -> String {
+    match color {
+        Some(color) if *COLOR => format!("{}{}\x1b[0m", color.escape(), text),
+        _ => text.to_string(),
+    }
+}
+
+/// The characters the box is drawn with
+pub(crate) struct Border {
+    pub(crate) top_left: &'static str,
+    pub(crate) top_right: &'static str,
+    pub(crate) bottom_left: &'static str,
+    pub(crate) bottom_right: &'static str,
+    pub(crate) horizontal: &'static str,
+    pub(crate) vertical: &'static str,
+}
+
+impl BorderStyle {
+    pub(crate) fn chars(self) -> Border {
+        let [top_left, top_right, bottom_left, bottom_right, horizontal, vertical] = match self {
+            BorderStyle::Rounded => ["╭", "╮", "╰", "╯", "─", "│"],
+            BorderStyle::Square => ["┌", "┐", "└", "┘", "─", "│"],
+            BorderStyle::Double => ["╔", "╗", "╚", "╝", "═", "║"],
+            BorderStyle::Ascii => ["+", "+", "+", "+", "-", "|"],
+            // Spaces keep the rows aligned as if there was a border
+            BorderStyle::None => [" "; 6],
+        };
+
+        Border {
+            top_left,
+            top_right,
+            bottom_left,
+            bottom_right,
+            horizontal,
+            vertical,
+        }
+    }
+}
+
+/// A built-in theme that `[theme] name` can refer to
+struct Preset {
+    name: &'static str,
+    border: BorderStyle,
+    border_color: Option<&'static str>,
+    title_color: Option<&'static str>,
+    label_color: Option<&'static str>,
+    value_color: Option<&'static str>,
+    warning_color: Option<&'static str>,
+    critical_color: Option<&'static str>,
+    /// The filled and empty parts of usage bars
+    bar: [&'static str; 2],
+}
+
+static PRESETS: [Preset; 6] = [
+    Preset {
+        name: "default",
+        border: BorderStyle::Rounded,
+        border_color: None,
+        title_color: Some("green"),
+        label_color: None,
+        value_color: None,
+        warning_color: Some("yellow"),
+        critical_color: Some("red"),
+        bar: ["█", "░"],
+    },
+    Preset {
+        name: "mono",
+        border: BorderStyle::Rounded,
+        border_color: None,
+        title_color: None,
+        label_color: None,
+        value_color: None,
+        warning_color: None,
+        critical_color: None,
+        bar: ["█", "░"],
+    },
+    Preset {
+        name: "nord",
+        border: BorderStyle::Rounded,
+        border_color: Some("#4c566a"),
+        title_color: Some("#88c0d0"),
+        label_color: Some("#81a1c1"),
+        value_color: Some("#d8dee9"),
+        warning_color: Some("#ebcb8b"),
+        critical_color: Some("#bf616a"),
+        bar: ["█", "░"],
+    },
+    Preset {
+        name: "dracula",
+        border: BorderStyle::Rounded,
+        border_color: Some("#6272a4"),
+        title_color: Some("#bd93f9"),
+        label_color: Some("#ff79c6"),
+        value_color: Some("#f8f8f2"),
+        warning_color: Some("#f1fa8c"),
+        critical_color: Some("#ff5555"),
+        bar: ["█", "░"],
+    },
+    Preset {
+        name: "gruvbox",
+        border: BorderStyle::Square,
+        border_color: Some("#928374"),
+        title_color: Some("#b8bb26"),
+        label_color: Some("#fabd2f"),
+        value_color: Some("#ebdbb2"),
+        warning_color: Some("#fabd2f"),
+        critical_color: Some("#fb4934"),
+        bar: ["█", "░"],
+    },
+    Preset {
+        name: "retro",
+        border: BorderStyle::Ascii,
+        border_color: Some("green"),
+        title_color: Some("bright_green"),
+        label_color: Some("bright_green"),
+        value_color: Some("green"),
+        warning_color: Some("bright_yellow"),
+        critical_color: Some("bright_red"),
+        bar: ["#", "-"],
+    },
+];
+
+/// The colors set for a single module. `Some(None)` turns off the theme's color.
+struct ModulePalette {
+    label: Option<Option<Color>>,
+    value: Option<Option<Color>>,
+}
+
+/// The theme in effect, a preset with the user's `[theme]` settings applied on top
+pub(crate) struct Palette {
+    pub(crate) border: Border,
+    pub(crate) border_color: Option<Color>,
+    pub(crate) title_color: Option<Color>,
+    label_color: Option<Color>,
+    value_color: Option<Color>,
+    pub(crate) warning_color: Option<Color>,
+    pub(crate) critical_color: Option<Color>,
+    pub(crate) bar_filled: String,
+    pub(crate) bar_empty: String,
+    modules: BTreeMap<String, ModulePalette>,
+}
+
+/// Picks the user's color over the preset's one. `none` turns a preset color off.
+pub(crate) fn color(user: Option<&str>, preset: Option<&str>) -> Option<Color> {
+    match user.or(preset)? {
+        "none" => None,
+        spec => spec
+            .parse()
+            .map_err(|e| tracing::warn!("Ignoring color: {}", e))
+            .ok(),
+    }
+}
+
+impl Palette {
+    pub(crate) fn new(theme: &Theme) -> Palette {
+        let preset = PRESETS
+            .iter()
+            .find(|p| p.name == theme.name)
+            .unwrap_or_else(|| {
+                tracing::warn!("Unknown theme {}, using the default one", theme.name);
+                &PRESETS[0]
+            });
+
+        let modules = theme
+            .modules
+            .iter()
+            .map(|(name, colors)| {
+                let palette = ModulePalette {
+                    label: colors.label.as_deref().map(|c| color(Some(c), None)),
+                    value: colors.value.as_deref().map(|c| color(Some(c), None)),
+                };
+                (name.clone(), palette)
+            })
+            .collect();
+
+        Palette {
+            border: theme.border.unwrap_or(preset.border).chars(),
+            border_color: color(theme.border_color.as_deref(), preset.border_color),
+            title_color: color(theme.title_color.as_deref(), preset.title_color),
+            label_color: color(theme.label_color.as_deref(), preset.label_color),
+            value_color: color(theme.value_color.as_deref(), preset.value_color),
+            warning_color: color(theme.warning_color.as_deref(), preset.warning_color),
+            critical_color: color(theme.critical_color.as_deref(), preset.critical_color),
+            bar_filled: theme
+                .bar_filled
+                .as_deref()
+                .unwrap_or(preset.bar[0])
+                .to_string(),
+            bar_empty: theme
+                .bar_empty
+                .as_deref()
+                .unwrap_or(preset.bar[1])
+                .to_string(),
+            modules,
+        }
+    }
+
+    /// The color of a module's icon
+    pub(crate) fn label(&self, module: &str) -> Option<&Color> {
+        match self.modules.get(module).and_then(|m| m.label.as_ref()) {
+            Some(label) => label.as_ref(),
+            None => self.label_color.as_ref(),
+        }
+    }
+
+    /// The color of a module's text
+    pub(crate) fn value(&self, module: &str) -> Option<&Color> {
+        match self.modules.get(module).and_then(|m| m.value.as_ref()) {
+            Some(value) => value.as_ref(),
+            None => self.value_color.as_ref(),
+        }
+    }
+}
@@ -0,0 +1,117 @@
+use {
+    crate::util::{module::Module, statics::LOCALE, template::fill},
+    std::{future::Future, os::unix::process::CommandExt, process, thread, time::Duration},
+    tokio::{
+        process::{Child, Command},
+        sync::oneshot,
+    },
+};
+
+/// A module that did not finish within its timeout, shown as a placeholder row
+pub(crate) struct TimedOut(pub(crate) &'static str);
+
+impl Module for TimedOut {
+    fn name(&self) -> &'static str {
+        self.0
+    }
+
+    fn text(&self) -> String {
+        fill(&LOCALE.get("timed_out"), &[("module", self.label())])
+    }
+}
+
+/// Awaits `future`, giving up after `timeout_ms` milliseconds unless that is 0.
+/// Dropping the future kills the child processes it holds a `ProcessGroup` for.
+pub(crate) async fn limit<T>(
+    module: &'static str,
+    timeout_ms: u64,
+    future: impl Future<Output = Option<T>>,
+) -> Result<Option<T>, TimedOut> {
+    if timeout_ms == 0 {
+        return Ok(future.await);
+    }
+
+    tokio::time::timeout(Duration::from_millis(timeout_ms), future)
+        .await
+        .map_err(|_| {
+            tracing::info!("{} took longer than {}ms, giving up", module, timeout_ms);
+            TimedOut(module)
+        })
+}
+
+/// Runs `collect` on a thread of its own and waits for it without blocking. For
+/// collectors that block or spin without yielding, which `limit` could not give up
+/// on otherwise. The thread is left running after a timeout rather than spawned
+/// with `spawn_blocking`, so that the runtime does not wait for it on shutdown.
+pub(crate) async fn detached<T: Send + 'static>(
+    collect: impl FnOnce() -> Option<T> + Send + 'static,
+) -> Option<T> {
+    let (tx, rx) = oneshot::channel();
+    thread::spawn(move || tx.send(collect()));
+
+    rx.await.ok().flatten()
+}
+
+/// `command` in a process group of its own, which `ProcessGroup` can kill as a whole
+pub(crate) fn own_group(mut command: process::Command) -> Command {
+    command.process_group(0);
+    Command::from(command)
+}
+
+/// Kills the process group of a child spawned with `own_group` when it is dropped,
+/// as happens when `limit` gives up on the future holding it. `kill_on_drop` only
+/// kills the child itself, while scripts like `checkupdates` leave the real work to
+/// processes they start.
+pub(crate) struct ProcessGroup(Option<i32>);
+
+impl ProcessGroup {
+    pub(crate) fn new(child: &Child) -> ProcessGroup {
+        ProcessGroup(child.id().map(|id| id as i32))
+    }
+
+    /// Keeps the group alive once the child has been waited for, as its id may be reused
+    pub(crate) fn release(mut self) {
+        self.0 = None;
+    }
+}
+
+impl Drop for ProcessGroup {
+    fn drop(&mut self) {
+        if let Some(group) = self.0 {
+            // A negative pid sends the signal to every process in the group
+            unsafe { libc::kill(-group, libc::SIGKILL) };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, std::time::Instant};
+
+    #[tokio::test]
+    async fn gives_up_on_collectors_that_never_yield() {
+        let start = Instant::now();
+        let result = limit(
+            "stuck",
+            100,
+            detached(|| {
+                thread::sleep(Duration::from_secs(30));
+                Some(())
+            }),
+        )
+        .await;
+
+        assert!(result.is_err());
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+
+    #[tokio::test]
+    async fn keeps_what_finishes_in_time() {
+        let result = limit("quick", 1000, detached(|| Some(42))).await;
+        assert!(matches!(result, Ok(Some(42))));
+        assert!(matches!(
+            limit("none", 0, detached(|| None::<u8>)).await,
+            Ok(None)
+        ));
+    }
+}